@@ -0,0 +1,2 @@
+-- migrations/20240101000003_plaid_sync_cursor.sql
+ALTER TABLE plaid_items ADD COLUMN transactions_cursor TEXT;
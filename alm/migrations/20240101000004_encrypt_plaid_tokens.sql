@@ -0,0 +1,6 @@
+-- migrations/20240101000004_encrypt_plaid_tokens.sql
+-- plaid_access_token now holds AES-GCM ciphertext sealed with a per-row data
+-- key. Rows without encryption_key_id predate encryption and are sealed by the
+-- `rotate-token-keys` command.
+ALTER TABLE plaid_items ADD COLUMN encrypted_data_key TEXT;
+ALTER TABLE plaid_items ADD COLUMN encryption_key_id VARCHAR(64);
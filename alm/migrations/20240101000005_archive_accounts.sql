@@ -0,0 +1,2 @@
+-- migrations/20240101000005_archive_accounts.sql
+ALTER TABLE accounts ADD COLUMN archived_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,9 @@
+-- migrations/20240101000006_institutions.sql
+CREATE TABLE institutions (
+institution_id VARCHAR(255) PRIMARY KEY,
+name VARCHAR(255) NOT NULL,
+logo TEXT,
+primary_color VARCHAR(20),
+url TEXT,
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+-- migrations/20240101000007_plaid_account_details.sql
+ALTER TABLE accounts
+ADD COLUMN account_subtype VARCHAR(100),
+ADD COLUMN available_balance DECIMAL(15, 2),
+ADD COLUMN credit_limit DECIMAL(15, 2),
+ADD COLUMN mask VARCHAR(10),
+ADD COLUMN official_name VARCHAR(255);
@@ -0,0 +1,10 @@
+-- migrations/20240101000008_fx_rates.sql
+ALTER TABLE users ADD COLUMN reporting_currency VARCHAR(10) NOT NULL DEFAULT 'USD';
+
+CREATE TABLE fx_rates (
+base_currency VARCHAR(10) NOT NULL,
+quote_currency VARCHAR(10) NOT NULL,
+rate_date DATE NOT NULL,
+rate DECIMAL(20, 10) NOT NULL CHECK (rate > 0),
+PRIMARY KEY (base_currency, quote_currency, rate_date)
+);
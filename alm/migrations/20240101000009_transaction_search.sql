@@ -0,0 +1,6 @@
+-- migrations/20240101000009_transaction_search.sql
+CREATE INDEX idx_transactions_search ON transactions
+USING GIN (to_tsvector('english', description || ' ' || COALESCE(merchant_name, '')));
+
+CREATE INDEX idx_transactions_date_id ON transactions(date, id);
+CREATE INDEX idx_transactions_amount_id ON transactions(amount, id);
@@ -0,0 +1,2 @@
+-- migrations/20240101000010_transaction_notes.sql
+ALTER TABLE transactions ADD COLUMN notes TEXT;
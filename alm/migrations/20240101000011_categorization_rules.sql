@@ -0,0 +1,21 @@
+-- migrations/20240101000011_categorization_rules.sql
+CREATE TABLE categorization_rules (
+id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+name VARCHAR(255) NOT NULL,
+priority INTEGER NOT NULL DEFAULT 0,
+match_type VARCHAR(20) NOT NULL DEFAULT 'contains' CHECK (match_type IN ('contains', 'regex')),
+merchant_pattern TEXT,
+description_pattern TEXT,
+min_amount DECIMAL(15, 2),
+max_amount DECIMAL(15, 2),
+account_id UUID REFERENCES accounts(id) ON DELETE CASCADE,
+set_category_id UUID REFERENCES categories(id) ON DELETE CASCADE,
+set_description TEXT,
+is_active BOOLEAN NOT NULL DEFAULT true,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+CHECK (set_category_id IS NOT NULL OR set_description IS NOT NULL)
+);
+
+CREATE INDEX idx_categorization_rules_user_id ON categorization_rules(user_id);
@@ -0,0 +1,41 @@
+-- migrations/20240101000012_plaid_category_mappings.sql
+ALTER TABLE transactions ADD COLUMN plaid_category_primary VARCHAR(100);
+ALTER TABLE transactions ADD COLUMN plaid_category_detailed VARCHAR(100);
+
+-- Keys are Plaid personal_finance_category codes, either a primary code or a
+-- more specific detailed one. Detailed codes win over their primary code.
+CREATE TABLE plaid_category_mappings (
+plaid_category VARCHAR(100) PRIMARY KEY,
+category_id UUID NOT NULL REFERENCES categories(id) ON DELETE CASCADE
+);
+
+INSERT INTO plaid_category_mappings (plaid_category, category_id)
+SELECT m.plaid_category, c.id
+FROM (VALUES
+('INCOME', 'Income'),
+('INCOME_WAGES', 'Salary'),
+('INCOME_DIVIDENDS', 'Investments'),
+('INCOME_INTEREST_EARNED', 'Investments'),
+('INCOME_RETIREMENT_PENSION', 'Investments'),
+('FOOD_AND_DRINK', 'Dining Out'),
+('FOOD_AND_DRINK_GROCERIES', 'Groceries'),
+('ENTERTAINMENT', 'Entertainment'),
+('ENTERTAINMENT_TV_AND_MOVIES', 'Subscriptions'),
+('ENTERTAINMENT_MUSIC_AND_AUDIO', 'Subscriptions'),
+('GENERAL_MERCHANDISE', 'Shopping'),
+('HOME_IMPROVEMENT', 'Housing'),
+('RENT_AND_UTILITIES', 'Utilities'),
+('RENT_AND_UTILITIES_RENT', 'Housing'),
+('LOAN_PAYMENTS', 'Other'),
+('LOAN_PAYMENTS_MORTGAGE_PAYMENT', 'Housing'),
+('MEDICAL', 'Health'),
+('PERSONAL_CARE', 'Personal Care'),
+('GENERAL_SERVICES', 'Other'),
+('GENERAL_SERVICES_EDUCATION', 'Education'),
+('GENERAL_SERVICES_INSURANCE', 'Insurance'),
+('TRANSPORTATION', 'Transportation'),
+('TRAVEL', 'Travel'),
+('BANK_FEES', 'Other'),
+('GOVERNMENT_AND_NON_PROFIT', 'Other')
+) AS m(plaid_category, category_name)
+JOIN categories c ON c.name = m.category_name AND c.is_default = true AND c.user_id IS NULL;
@@ -0,0 +1,24 @@
+-- migrations/20240101000013_transaction_splits.sql
+CREATE TABLE transaction_splits (
+id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+category_id UUID REFERENCES categories(id) ON DELETE SET NULL,
+amount DECIMAL(15, 2) NOT NULL CHECK (amount <> 0),
+memo TEXT,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_transaction_splits_transaction_id ON transaction_splits(transaction_id);
+CREATE INDEX idx_transaction_splits_category_id ON transaction_splits(category_id);
+
+-- What each transaction's amount is allocated to: its splits when it has
+-- any, otherwise the transaction itself. Reports aggregate over this.
+CREATE VIEW transaction_allocations AS
+SELECT t.id AS transaction_id, t.account_id, t.date, t.category_id, t.amount
+FROM transactions t
+WHERE NOT EXISTS (SELECT 1 FROM transaction_splits s WHERE s.transaction_id = t.id)
+UNION ALL
+SELECT s.transaction_id, t.account_id, t.date, s.category_id, s.amount
+FROM transaction_splits s
+JOIN transactions t ON s.transaction_id = t.id;
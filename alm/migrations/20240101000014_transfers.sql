@@ -0,0 +1,39 @@
+-- migrations/20240101000014_transfers.sql
+-- Pairs of transactions that move money between the user's own accounts.
+-- Unlinked pairs are kept so detection does not link them again.
+CREATE TABLE transfers (
+id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+outflow_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+inflow_transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+status VARCHAR(20) NOT NULL DEFAULT 'linked' CHECK (status IN ('linked', 'unlinked')),
+source VARCHAR(20) NOT NULL CHECK (source IN ('detected', 'manual')),
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+UNIQUE (outflow_transaction_id, inflow_transaction_id),
+CHECK (outflow_transaction_id <> inflow_transaction_id)
+);
+
+CREATE INDEX idx_transfers_user_id ON transfers(user_id);
+CREATE UNIQUE INDEX idx_transfers_linked_outflow ON transfers(outflow_transaction_id) WHERE status = 'linked';
+CREATE UNIQUE INDEX idx_transfers_linked_inflow ON transfers(inflow_transaction_id) WHERE status = 'linked';
+
+-- Transfers are neither income nor spending, so reports leave them out.
+CREATE OR REPLACE VIEW transaction_allocations AS
+SELECT t.id AS transaction_id, t.account_id, t.date, t.category_id, t.amount
+FROM transactions t
+WHERE NOT EXISTS (SELECT 1 FROM transaction_splits s WHERE s.transaction_id = t.id)
+AND NOT EXISTS (
+    SELECT 1 FROM transfers tr
+    WHERE tr.status = 'linked'
+    AND (tr.outflow_transaction_id = t.id OR tr.inflow_transaction_id = t.id)
+)
+UNION ALL
+SELECT s.transaction_id, t.account_id, t.date, s.category_id, s.amount
+FROM transaction_splits s
+JOIN transactions t ON s.transaction_id = t.id
+WHERE NOT EXISTS (
+    SELECT 1 FROM transfers tr
+    WHERE tr.status = 'linked'
+    AND (tr.outflow_transaction_id = t.id OR tr.inflow_transaction_id = t.id)
+);
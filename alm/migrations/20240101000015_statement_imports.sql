@@ -0,0 +1,25 @@
+-- migrations/20240101000015_statement_imports.sql
+-- Identifies a transaction imported from a statement file: the OFX FITID or a
+-- hash of the CSV row, so importing an overlapping statement adds nothing twice.
+ALTER TABLE transactions ADD COLUMN import_id TEXT;
+
+CREATE UNIQUE INDEX idx_transactions_import_id ON transactions(account_id, import_id)
+WHERE import_id IS NOT NULL;
+
+-- How to read an account's CSV exports. Columns are numbered from 0.
+CREATE TABLE import_profiles (
+account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+delimiter VARCHAR(1) NOT NULL DEFAULT ',',
+has_header BOOLEAN NOT NULL DEFAULT true,
+date_column INTEGER NOT NULL CHECK (date_column >= 0),
+date_format VARCHAR(50) NOT NULL DEFAULT '%Y-%m-%d',
+description_column INTEGER NOT NULL CHECK (description_column >= 0),
+amount_column INTEGER CHECK (amount_column >= 0),
+debit_column INTEGER CHECK (debit_column >= 0),
+credit_column INTEGER CHECK (credit_column >= 0),
+negate_amounts BOOLEAN NOT NULL DEFAULT false,
+decimal_comma BOOLEAN NOT NULL DEFAULT false,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+CHECK (amount_column IS NOT NULL OR debit_column IS NOT NULL OR credit_column IS NOT NULL)
+);
@@ -0,0 +1,14 @@
+-- migrations/20240101000016_account_balance_entries.sql
+-- Dated balances recorded for manual accounts. An account's balance is its
+-- latest entry, plus the transactions since then when it is derived from them.
+CREATE TABLE account_balance_entries (
+id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+date DATE NOT NULL,
+balance DECIMAL(15, 2) NOT NULL,
+note TEXT,
+created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+UNIQUE (account_id, date)
+);
+
+ALTER TABLE accounts ADD COLUMN balance_from_transactions BOOLEAN NOT NULL DEFAULT false;
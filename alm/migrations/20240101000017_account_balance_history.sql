@@ -0,0 +1,13 @@
+-- migrations/20240101000017_account_balance_history.sql
+-- One balance per account and day. Sync and manual updates record the day's
+-- balance as it happens; days before that are backfilled by walking the
+-- account's transactions back from its current balance, and never replace a
+-- recorded balance.
+CREATE TABLE account_balance_history (
+account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+date DATE NOT NULL,
+balance DECIMAL(15, 2) NOT NULL,
+source VARCHAR(20) NOT NULL CHECK (source IN ('sync', 'manual', 'backfill')),
+updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+PRIMARY KEY (account_id, date)
+);
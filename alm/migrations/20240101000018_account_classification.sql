@@ -0,0 +1,6 @@
+-- migrations/20240101000018_account_classification.sql
+-- The user's choice of whether an account is an asset or a liability. When
+-- unset, credit and loan accounts are liabilities and everything else is an
+-- asset.
+ALTER TABLE accounts ADD COLUMN classification VARCHAR(20)
+CHECK (classification IN ('asset', 'liability'));
@@ -0,0 +1,5 @@
+-- migrations/20240101000019_week_start_day.sql
+-- The day weekly analytics start on, as an ISO weekday: 1 is Monday (ISO
+-- weeks) and 7 is Sunday.
+ALTER TABLE users ADD COLUMN week_start_day SMALLINT NOT NULL DEFAULT 1
+CHECK (week_start_day BETWEEN 1 AND 7);
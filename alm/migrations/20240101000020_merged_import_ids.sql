@@ -0,0 +1,8 @@
+-- migrations/20240101000020_merged_import_ids.sql
+-- Import ids of statement lines merged into another transaction that already
+-- had its own, so importing either statement again skips the line.
+CREATE TABLE merged_import_ids (
+transaction_id UUID NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+import_id TEXT NOT NULL,
+PRIMARY KEY (transaction_id, import_id)
+);
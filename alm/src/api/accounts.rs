@@ -1,26 +1,77 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get},
+    extract::{FromRef, Path, State},
+    routing::{delete, get, post},
     Json, Router,
 };
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
 
+use super::transactions::nullable;
 use crate::{
-    db::{models::Account, DbPool},
-    utils::{auth::AuthUser, AppError},
+    balances::{
+        self,
+        history::{self, Source},
+    },
+    db::{
+        models::{Account, Institution},
+        DbPool,
+    },
+    fx,
+    plaid::{
+        sync::{self, SyncSummary},
+        PlaidClient,
+    },
+    utils::{auth::AuthUser, crypto::KeyRing, AppError},
 };
 
-pub fn routes(pool: DbPool) -> Router {
+#[derive(Clone)]
+struct AccountsState {
+    pool: DbPool,
+    plaid: PlaidClient,
+    keys: Arc<KeyRing>,
+}
+
+impl FromRef<AccountsState> for DbPool {
+    fn from_ref(state: &AccountsState) -> Self {
+        state.pool.clone()
+    }
+}
+
+pub fn routes(pool: DbPool, plaid: PlaidClient, keys: Arc<KeyRing>) -> Router {
+    let state = AccountsState {
+        pool: pool.clone(),
+        plaid,
+        keys,
+    };
+
     Router::new()
-        .route("/", get(list_accounts))
-        .route("/:id", get(get_account).delete(delete_account))
-        .with_state(pool)
+        .route("/", get(list_accounts).post(create_account))
+        .route(
+            "/:id",
+            get(get_account).put(update_account).delete(delete_account),
+        )
+        .route("/:id/sync", post(sync_account))
+        .with_state(state)
+        .merge(super::balances::routes(pool.clone()))
+        .merge(super::imports::routes(pool))
 }
 
+#[derive(Serialize)]
+struct AccountResponse {
+    #[serde(flatten)]
+    account: Account,
+    institution: Option<Institution>,
+}
+
+/// Lists accounts with their bank's metadata attached, so clients can group
+/// them by institution. Manual accounts have no institution.
 async fn list_accounts(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
-) -> Result<Json<Vec<Account>>, AppError> {
+) -> Result<Json<Vec<AccountResponse>>, AppError> {
     let accounts = sqlx::query_as!(
         Account,
         "SELECT * FROM accounts WHERE user_id = $1 ORDER BY created_at DESC",
@@ -29,6 +80,47 @@ async fn list_accounts(
     .fetch_all(&pool)
     .await?;
 
+    let institutions: HashMap<String, Institution> = sqlx::query!(
+        "SELECT p.plaid_item_id, i.institution_id, i.name, i.logo, i.primary_color, i.url, i.updated_at
+         FROM plaid_items p
+         JOIN institutions i ON i.institution_id = p.institution_id
+         WHERE p.user_id = $1",
+        user_id
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        (
+            row.plaid_item_id,
+            Institution {
+                institution_id: row.institution_id,
+                name: row.name,
+                logo: row.logo,
+                primary_color: row.primary_color,
+                url: row.url,
+                updated_at: row.updated_at,
+            },
+        )
+    })
+    .collect();
+
+    let accounts = accounts
+        .into_iter()
+        .map(|account| {
+            let institution = account
+                .plaid_item_id
+                .as_ref()
+                .and_then(|id| institutions.get(id))
+                .cloned();
+
+            AccountResponse {
+                account,
+                institution,
+            }
+        })
+        .collect();
+
     Ok(Json(accounts))
 }
 
@@ -50,6 +142,240 @@ async fn get_account(
     Ok(Json(account))
 }
 
+/// Account types a manual account can have; Plaid accounts use Plaid's own.
+const ACCOUNT_TYPES: &[&str] = &[
+    "depository",
+    "credit",
+    "loan",
+    "investment",
+    "cash",
+    "property",
+    "other",
+];
+
+fn check_account_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("Account name is required".to_string()));
+    }
+    Ok(())
+}
+
+fn check_account_type(account_type: &str) -> Result<(), AppError> {
+    if !ACCOUNT_TYPES.contains(&account_type) {
+        return Err(AppError::BadRequest(format!(
+            "account_type must be one of {}",
+            ACCOUNT_TYPES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Upper-cases a currency code. Crypto and other unofficial codes can be
+/// longer than ISO 4217's three letters.
+fn normalize_currency(currency: &str) -> Result<String, AppError> {
+    let currency = currency.trim().to_uppercase();
+
+    if !(3..=10).contains(&currency.len()) || !currency.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::BadRequest(
+            "currency must be a currency code such as USD".to_string(),
+        ));
+    }
+    Ok(currency)
+}
+
+fn check_classification(classification: &str) -> Result<(), AppError> {
+    if !matches!(classification, "asset" | "liability") {
+        return Err(AppError::BadRequest(
+            "classification must be asset or liability".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct CreateAccountRequest {
+    account_name: String,
+    account_type: String,
+    account_subtype: Option<String>,
+    /// Defaults to the user's reporting currency.
+    currency: Option<String>,
+    /// Opening balance, recorded as the account's first balance entry.
+    balance: Option<Decimal>,
+    balance_date: Option<NaiveDate>,
+    #[serde(default)]
+    balance_from_transactions: bool,
+    /// Overrides the asset or liability classification implied by the type.
+    classification: Option<String>,
+}
+
+/// Creates an account that is not linked to Plaid, such as cash, property,
+/// a loan or an offline brokerage account.
+async fn create_account(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(payload): Json<CreateAccountRequest>,
+) -> Result<Json<Account>, AppError> {
+    check_account_name(&payload.account_name)?;
+    check_account_type(&payload.account_type)?;
+    if let Some(classification) = &payload.classification {
+        check_classification(classification)?;
+    }
+
+    let currency = match &payload.currency {
+        Some(currency) => normalize_currency(currency)?,
+        None => fx::reporting_currency(&pool, user_id).await?,
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let account = sqlx::query_as!(
+        Account,
+        "INSERT INTO accounts
+            (user_id, account_name, account_type, account_subtype, currency, balance,
+             balance_from_transactions, classification)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING *",
+        user_id,
+        payload.account_name.trim(),
+        payload.account_type,
+        payload.account_subtype,
+        currency,
+        payload.balance.unwrap_or_default(),
+        payload.balance_from_transactions,
+        payload.classification
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let balance_date = payload
+        .balance_date
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    if let Some(balance) = payload.balance {
+        sqlx::query!(
+            "INSERT INTO account_balance_entries (account_id, date, balance, note)
+             VALUES ($1, $2, $3, 'Opening balance')",
+            account.id,
+            balance_date,
+            balance
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    if let Some(balance) = payload.balance {
+        history::record(&pool, account.id, balance_date, balance, Source::Manual).await?;
+    }
+
+    Ok(Json(account))
+}
+
+/// The balance itself changes through balance entries, not here. Accounts
+/// linked to Plaid only take `classification`; `null` goes back to
+/// classifying by type.
+#[derive(Deserialize)]
+struct UpdateAccountRequest {
+    account_name: Option<String>,
+    account_type: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    account_subtype: Option<Option<String>>,
+    currency: Option<String>,
+    balance_from_transactions: Option<bool>,
+    #[serde(default, deserialize_with = "nullable")]
+    classification: Option<Option<String>>,
+}
+
+impl UpdateAccountRequest {
+    fn changes_details(&self) -> bool {
+        self.account_name.is_some()
+            || self.account_type.is_some()
+            || self.account_subtype.is_some()
+            || self.currency.is_some()
+            || self.balance_from_transactions.is_some()
+    }
+}
+
+async fn update_account(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateAccountRequest>,
+) -> Result<Json<Account>, AppError> {
+    if let Some(name) = &payload.account_name {
+        check_account_name(name)?;
+    }
+    if let Some(account_type) = &payload.account_type {
+        check_account_type(account_type)?;
+    }
+    if let Some(Some(classification)) = &payload.classification {
+        check_classification(classification)?;
+    }
+    let currency = payload
+        .currency
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let plaid_account_id = sqlx::query_scalar!(
+        "SELECT plaid_account_id FROM accounts WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if plaid_account_id.is_some() && payload.changes_details() {
+        return Err(AppError::BadRequest(
+            "Accounts linked to Plaid are updated by sync; only classification can be changed"
+                .to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE accounts SET
+            account_name = COALESCE($3, account_name),
+            account_type = COALESCE($4, account_type),
+            account_subtype = CASE WHEN $5 THEN $6 ELSE account_subtype END,
+            currency = COALESCE($7, currency),
+            balance_from_transactions = COALESCE($8, balance_from_transactions),
+            classification = CASE WHEN $9 THEN $10 ELSE classification END
+         WHERE id = $1 AND user_id = $2",
+        id,
+        user_id,
+        payload.account_name.as_deref().map(str::trim),
+        payload.account_type,
+        payload.account_subtype.is_some(),
+        payload.account_subtype.flatten(),
+        currency,
+        payload.balance_from_transactions,
+        payload.classification.is_some(),
+        payload.classification.clone().flatten()
+    )
+    .execute(&pool)
+    .await?;
+
+    // These decide which way transactions move the balance.
+    if payload.balance_from_transactions.is_some()
+        || payload.account_type.is_some()
+        || payload.classification.is_some()
+    {
+        if plaid_account_id.is_some() {
+            history::backfill(&pool, id).await?;
+        } else {
+            balances::refresh(&pool, id).await?;
+        }
+    }
+
+    let account = sqlx::query_as!(Account, "SELECT * FROM accounts WHERE id = $1", id)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(account))
+}
+
 async fn delete_account(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
@@ -65,3 +391,26 @@ async fn delete_account(
 
     Ok(Json(()))
 }
+
+async fn sync_account(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<AccountsState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SyncSummary>, AppError> {
+    let item_id = sqlx::query_scalar!(
+        "SELECT p.id FROM accounts a
+         JOIN plaid_items p ON p.plaid_item_id = a.plaid_item_id AND p.user_id = a.user_id
+         WHERE a.id = $1 AND a.user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::BadRequest(
+        "Account is not linked to Plaid".to_string(),
+    ))?;
+
+    let summary = sync::sync_item(&state.pool, &state.plaid, &state.keys, item_id).await?;
+
+    Ok(Json(summary))
+}
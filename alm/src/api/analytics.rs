@@ -3,114 +3,376 @@ use axum::{
     routing::get,
     Json, Router,
 };
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
 use uuid::Uuid;
 
 use crate::{
+    balances::Classification,
     db::DbPool,
+    fx::{self, CurrencyAmount, FxRates, MixedTotal},
     utils::{auth::AuthUser, AppError},
 };
 
 pub fn routes(pool: DbPool) -> Router {
     Router::new()
         .route("/net-worth", get(net_worth))
+        .route("/net-worth/history", get(net_worth_history))
         .route("/spending-by-category", get(spending_by_category))
         .route("/income-over-time", get(income_over_time))
         .route("/spending-over-time", get(spending_over_time))
         .with_state(pool)
 }
 
+/// Assets less liabilities. A liability's balance is what is owed, so it is
+/// subtracted.
+#[derive(Default, Serialize)]
+struct BalanceSheet {
+    assets: Decimal,
+    liabilities: Decimal,
+    total: Decimal,
+}
+
+impl BalanceSheet {
+    fn add(&mut self, classification: Classification, balance: Decimal) {
+        match classification {
+            Classification::Asset => {
+                self.assets += balance;
+                self.total += balance;
+            }
+            Classification::Liability => {
+                self.liabilities += balance;
+                self.total -= balance;
+            }
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct NetWorthResponse {
-    total: f64,
+    currency: String,
+    #[serde(flatten)]
+    totals: BalanceSheet,
+    by_type: Vec<AccountTypeTotal>,
     accounts: Vec<AccountBalance>,
 }
 
+#[derive(Serialize)]
+struct AccountTypeTotal {
+    account_type: String,
+    classification: Classification,
+    total: Decimal,
+}
+
 #[derive(Serialize)]
 struct AccountBalance {
     account_id: Uuid,
     account_name: String,
-    balance: f64,
+    account_type: String,
+    classification: Classification,
+    currency: String,
+    balance: Decimal,
+    converted_balance: Decimal,
 }
 
+/// Current balances converted to the user's reporting currency at the latest
+/// available rate, totalled as assets and liabilities and by account type.
 async fn net_worth(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
 ) -> Result<Json<NetWorthResponse>, AppError> {
+    let reporting_currency = fx::reporting_currency(&pool, user_id).await?;
+
     let accounts = sqlx::query!(
-        "SELECT id, account_name, balance FROM accounts WHERE user_id = $1",
+        "SELECT id, account_name, account_type, classification, balance, currency
+         FROM accounts WHERE user_id = $1 AND archived_at IS NULL",
         user_id
     )
     .fetch_all(&pool)
     .await?;
 
-    let total: f64 = accounts.iter().map(|a| a.balance).sum();
+    let today = Utc::now().date_naive();
+    let currencies = fx::currency_set(accounts.iter().map(|a| &a.currency), &reporting_currency);
+    let rates = FxRates::load(&pool, &currencies, today, today).await?;
 
-    let account_balances = accounts
-        .into_iter()
-        .map(|a| AccountBalance {
+    let mut totals = BalanceSheet::default();
+    let mut by_type: BTreeMap<(Classification, String), Decimal> = BTreeMap::new();
+    let mut account_balances = Vec::with_capacity(accounts.len());
+
+    for a in accounts {
+        let classification = Classification::of(&a.account_type, a.classification.as_deref());
+        let converted_balance =
+            rates.convert(a.balance, &a.currency, &reporting_currency, today)?;
+
+        totals.add(classification, converted_balance);
+        *by_type
+            .entry((classification, a.account_type.clone()))
+            .or_default() += converted_balance;
+
+        account_balances.push(AccountBalance {
             account_id: a.id,
             account_name: a.account_name,
+            account_type: a.account_type,
+            classification,
+            currency: a.currency,
             balance: a.balance,
+            converted_balance,
+        });
+    }
+
+    let by_type = by_type
+        .into_iter()
+        .map(|((classification, account_type), total)| AccountTypeTotal {
+            account_type,
+            classification,
+            total,
         })
         .collect();
 
     Ok(Json(NetWorthResponse {
-        total,
+        currency: reporting_currency,
+        totals,
+        by_type,
         accounts: account_balances,
     }))
 }
 
+/// Longest range the net worth history covers in one request.
+const MAX_HISTORY_DAYS: i64 = 3660;
+
+#[derive(Serialize)]
+struct HistoryAccount {
+    account_id: Uuid,
+    account_name: String,
+    account_type: String,
+    currency: String,
+    classification: Classification,
+}
+
+#[derive(Serialize)]
+struct HistoryBalance {
+    account_id: Uuid,
+    balance: Decimal,
+    converted_balance: Decimal,
+}
+
+#[derive(Serialize)]
+struct NetWorthPoint {
+    date: NaiveDate,
+    #[serde(flatten)]
+    totals: BalanceSheet,
+    accounts: Vec<HistoryBalance>,
+}
+
+#[derive(Serialize)]
+struct NetWorthHistoryResponse {
+    currency: String,
+    accounts: Vec<HistoryAccount>,
+    history: Vec<NetWorthPoint>,
+}
+
+/// Daily net worth from the balance history, each day converted at that
+/// day's rate. An account's last known balance carries forward over days
+/// without one; before its first it is left out.
+async fn net_worth_history(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Query(query): Query<DateRangeQuery>,
+) -> Result<Json<NetWorthHistoryResponse>, AppError> {
+    if (query.end_date - query.start_date).num_days() > MAX_HISTORY_DAYS {
+        return Err(AppError::BadRequest(format!(
+            "Net worth history covers at most {} days at a time",
+            MAX_HISTORY_DAYS
+        )));
+    }
+
+    let reporting_currency = fx::reporting_currency(&pool, user_id).await?;
+
+    let accounts = sqlx::query!(
+        "SELECT id, account_name, account_type, classification, currency FROM accounts
+         WHERE user_id = $1 AND (archived_at IS NULL OR archived_at::date > $2)
+         ORDER BY created_at, id",
+        user_id,
+        query.start_date
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let balances = sqlx::query!(
+        "SELECT d::date as \"date!\", h.account_id, h.balance
+         FROM generate_series($2::date, $3::date, '1 day') d
+         CROSS JOIN accounts a
+         JOIN LATERAL (
+            SELECT account_id, balance FROM account_balance_history
+            WHERE account_id = a.id AND date <= d::date
+            ORDER BY date DESC
+            LIMIT 1
+         ) h ON true
+         WHERE a.user_id = $1 AND (a.archived_at IS NULL OR d::date < a.archived_at::date)
+         ORDER BY d, a.created_at, a.id",
+        user_id,
+        query.start_date,
+        query.end_date
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let currencies = fx::currency_set(accounts.iter().map(|a| &a.currency), &reporting_currency);
+    let rates = FxRates::load(&pool, &currencies, query.start_date, query.end_date).await?;
+    let accounts_by_id: HashMap<Uuid, _> = accounts.iter().map(|a| (a.id, a)).collect();
+
+    let mut history: Vec<NetWorthPoint> = Vec::new();
+
+    for b in balances {
+        let Some(account) = accounts_by_id.get(&b.account_id) else {
+            continue;
+        };
+
+        if history.last().is_none_or(|p| p.date != b.date) {
+            history.push(NetWorthPoint {
+                date: b.date,
+                totals: BalanceSheet::default(),
+                accounts: Vec::new(),
+            });
+        }
+        let point = history.last_mut().expect("pushed above");
+
+        let converted_balance =
+            rates.convert(b.balance, &account.currency, &reporting_currency, b.date)?;
+
+        point.totals.add(
+            Classification::of(&account.account_type, account.classification.as_deref()),
+            converted_balance,
+        );
+
+        point.accounts.push(HistoryBalance {
+            account_id: b.account_id,
+            balance: b.balance,
+            converted_balance,
+        });
+    }
+
+    let accounts = accounts
+        .into_iter()
+        .map(|a| HistoryAccount {
+            classification: Classification::of(&a.account_type, a.classification.as_deref()),
+            account_id: a.id,
+            account_name: a.account_name,
+            account_type: a.account_type,
+            currency: a.currency,
+        })
+        .collect();
+
+    Ok(Json(NetWorthHistoryResponse {
+        currency: reporting_currency,
+        accounts,
+        history,
+    }))
+}
+
 #[derive(Serialize)]
 struct CategorySpending {
     category_id: Option<Uuid>,
     category_name: Option<String>,
-    total: f64,
-    percentage: f64,
+    currency: String,
+    total: Decimal,
+    original: Vec<CurrencyAmount>,
+    percentage: Decimal,
 }
 
 #[derive(Deserialize)]
 struct DateRangeQuery {
     start_date: NaiveDate,
     end_date: NaiveDate,
+    /// Only used by the over-time reports.
+    #[serde(default)]
+    granularity: Granularity,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Granularity {
+    fn unit(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+            Granularity::Year => "year",
+        }
+    }
 }
 
+/// How dates are truncated to buckets. Postgres weeks are ISO weeks starting
+/// on Monday, so other week start days shift dates by `offset` days before
+/// truncating and back after.
+struct Buckets {
+    unit: &'static str,
+    offset: i32,
+}
+
+impl Buckets {
+    async fn load(
+        pool: &DbPool,
+        user_id: Uuid,
+        granularity: Granularity,
+    ) -> Result<Self, AppError> {
+        let offset = match granularity {
+            Granularity::Week => {
+                let week_start_day =
+                    sqlx::query_scalar!("SELECT week_start_day FROM users WHERE id = $1", user_id)
+                        .fetch_one(pool)
+                        .await?;
+
+                i32::from(week_start_day) - 1
+            }
+            _ => 0,
+        };
+
+        Ok(Buckets {
+            unit: granularity.unit(),
+            offset,
+        })
+    }
+}
+
+/// Spending per category in the reporting currency, each day's spending
+/// converted at that day's rate. Split transactions count towards each
+/// split's category.
 async fn spending_by_category(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
     Query(query): Query<DateRangeQuery>,
 ) -> Result<Json<Vec<CategorySpending>>, AppError> {
-    let total_spending = sqlx::query_scalar!(
-        "SELECT COALESCE(SUM(ABS(t.amount)), 0) as total
-         FROM transactions t
-         JOIN accounts a ON t.account_id = a.id
-         WHERE a.user_id = $1
-         AND t.date >= $2
-         AND t.date <= $3
-         AND t.amount < 0",
-        user_id,
-        query.start_date,
-        query.end_date
-    )
-    .fetch_one(&pool)
-    .await?
-    .unwrap_or(0.0);
+    let reporting_currency = fx::reporting_currency(&pool, user_id).await?;
 
     let spending = sqlx::query!(
         "SELECT 
             t.category_id,
-            c.name as category_name,
-            SUM(ABS(t.amount)) as total
-         FROM transactions t
+            c.name as \"category_name?\",
+            a.currency,
+            t.date as \"date!\",
+            SUM(ABS(t.amount)) as \"total!\"
+         FROM transaction_allocations t
          JOIN accounts a ON t.account_id = a.id
          LEFT JOIN categories c ON t.category_id = c.id
          WHERE a.user_id = $1
          AND t.date >= $2
          AND t.date <= $3
          AND t.amount < 0
-         GROUP BY t.category_id, c.name
-         ORDER BY total DESC",
+         GROUP BY t.category_id, c.name, a.currency, t.date",
         user_id,
         query.start_date,
         query.end_date
@@ -118,30 +380,48 @@ async fn spending_by_category(
     .fetch_all(&pool)
     .await?;
 
-    let result = spending
+    let currencies = fx::currency_set(spending.iter().map(|s| &s.currency), &reporting_currency);
+    let rates = FxRates::load(&pool, &currencies, query.start_date, query.end_date).await?;
+
+    let mut totals: HashMap<Option<Uuid>, (Option<String>, MixedTotal)> = HashMap::new();
+
+    for s in spending {
+        let (_, total) = totals
+            .entry(s.category_id)
+            .or_insert_with(|| (s.category_name, MixedTotal::default()));
+
+        total.add(&rates, s.total, &s.currency, &reporting_currency, s.date)?;
+    }
+
+    let total_spending: Decimal = totals.values().map(|(_, t)| t.converted).sum();
+
+    let mut result: Vec<CategorySpending> = totals
         .into_iter()
-        .map(|s| {
-            let total = s.total.unwrap_or(0.0);
-            CategorySpending {
-                category_id: s.category_id,
-                category_name: s.category_name,
-                total,
-                percentage: if total_spending > 0.0 {
-                    (total / total_spending) * 100.0
-                } else {
-                    0.0
-                },
-            }
+        .map(|(category_id, (category_name, total))| CategorySpending {
+            category_id,
+            category_name,
+            currency: reporting_currency.clone(),
+            total: total.converted,
+            original: total.original(),
+            percentage: if total_spending > Decimal::ZERO {
+                (total.converted / total_spending * Decimal::ONE_HUNDRED).round_dp(2)
+            } else {
+                Decimal::ZERO
+            },
         })
         .collect();
 
+    result.sort_by_key(|c| Reverse(c.total));
+
     Ok(Json(result))
 }
 
 #[derive(Serialize)]
 struct TimeSeriesData {
     date: NaiveDate,
-    amount: f64,
+    currency: String,
+    amount: Decimal,
+    original: Vec<CurrencyAmount>,
 }
 
 async fn income_over_time(
@@ -149,34 +429,39 @@ async fn income_over_time(
     State(pool): State<DbPool>,
     Query(query): Query<DateRangeQuery>,
 ) -> Result<Json<Vec<TimeSeriesData>>, AppError> {
+    let buckets = Buckets::load(&pool, user_id, query.granularity).await?;
+
     let data = sqlx::query!(
         "SELECT 
-            t.date,
-            SUM(t.amount) as amount
-         FROM transactions t
+            date_trunc($4, (t.date - $5::int)::timestamp)::date + $5::int as \"bucket!\",
+            t.date as \"date!\",
+            a.currency,
+            SUM(t.amount) as \"amount!\"
+         FROM transaction_allocations t
          JOIN accounts a ON t.account_id = a.id
          WHERE a.user_id = $1
          AND t.date >= $2
          AND t.date <= $3
          AND t.amount > 0
-         GROUP BY t.date
+         GROUP BY 1, t.date, a.currency
          ORDER BY t.date",
         user_id,
         query.start_date,
-        query.end_date
+        query.end_date,
+        buckets.unit,
+        buckets.offset
     )
     .fetch_all(&pool)
     .await?;
 
-    let result = data
+    let data = data
         .into_iter()
-        .map(|d| TimeSeriesData {
-            date: d.date,
-            amount: d.amount.unwrap_or(0.0),
-        })
+        .map(|d| (d.bucket, d.date, d.currency, d.amount))
         .collect();
 
-    Ok(Json(result))
+    Ok(Json(
+        time_series(&pool, user_id, &query, &buckets, data).await?,
+    ))
 }
 
 async fn spending_over_time(
@@ -184,32 +469,91 @@ async fn spending_over_time(
     State(pool): State<DbPool>,
     Query(query): Query<DateRangeQuery>,
 ) -> Result<Json<Vec<TimeSeriesData>>, AppError> {
+    let buckets = Buckets::load(&pool, user_id, query.granularity).await?;
+
     let data = sqlx::query!(
         "SELECT 
-            t.date,
-            SUM(ABS(t.amount)) as amount
-         FROM transactions t
+            date_trunc($4, (t.date - $5::int)::timestamp)::date + $5::int as \"bucket!\",
+            t.date as \"date!\",
+            a.currency,
+            SUM(ABS(t.amount)) as \"amount!\"
+         FROM transaction_allocations t
          JOIN accounts a ON t.account_id = a.id
          WHERE a.user_id = $1
          AND t.date >= $2
          AND t.date <= $3
          AND t.amount < 0
-         GROUP BY t.date
+         GROUP BY 1, t.date, a.currency
          ORDER BY t.date",
         user_id,
         query.start_date,
-        query.end_date
+        query.end_date,
+        buckets.unit,
+        buckets.offset
     )
     .fetch_all(&pool)
     .await?;
 
-    let result = data
+    let data = data
         .into_iter()
-        .map(|d| TimeSeriesData {
-            date: d.date,
-            amount: d.amount.unwrap_or(0.0),
-        })
+        .map(|d| (d.bucket, d.date, d.currency, d.amount))
         .collect();
 
-    Ok(Json(result))
+    Ok(Json(
+        time_series(&pool, user_id, &query, &buckets, data).await?,
+    ))
+}
+
+/// Folds per-day, per-currency sums into one converted point per bucket,
+/// dated at the bucket's first day. Each day is converted at its own rate,
+/// and buckets without transactions are zero. The first bucket may start
+/// before `start_date` but only counts transactions from it on.
+async fn time_series(
+    pool: &DbPool,
+    user_id: Uuid,
+    query: &DateRangeQuery,
+    buckets: &Buckets,
+    data: Vec<(NaiveDate, NaiveDate, String, Decimal)>,
+) -> Result<Vec<TimeSeriesData>, AppError> {
+    let reporting_currency = fx::reporting_currency(pool, user_id).await?;
+    let currencies = fx::currency_set(data.iter().map(|(_, _, c, _)| c), &reporting_currency);
+    let rates = FxRates::load(pool, &currencies, query.start_date, query.end_date).await?;
+
+    let mut totals: BTreeMap<NaiveDate, MixedTotal> = sqlx::query_scalar!(
+        "SELECT (b::date + $4::int) as \"bucket!\"
+         FROM generate_series(
+            date_trunc($1, ($2::date - $4::int)::timestamp),
+            ($3::date - $4::int)::timestamp,
+            ('1 ' || $1)::interval
+         ) b",
+        buckets.unit,
+        query.start_date,
+        query.end_date,
+        buckets.offset
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|bucket| (bucket, MixedTotal::default()))
+    .collect();
+
+    for (bucket, date, currency, amount) in data {
+        totals.entry(bucket).or_default().add(
+            &rates,
+            amount,
+            &currency,
+            &reporting_currency,
+            date,
+        )?;
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|(date, total)| TimeSeriesData {
+            date,
+            currency: reporting_currency.clone(),
+            amount: total.converted,
+            original: total.original(),
+        })
+        .collect())
 }
@@ -0,0 +1,145 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    balances::{self, history},
+    db::{models::BalanceEntry, DbPool},
+    utils::{auth::AuthUser, AppError},
+};
+
+/// Mounted under `/api/accounts`.
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route(
+            "/:id/balances",
+            get(list_balance_entries).post(record_balance),
+        )
+        .route("/:id/balances/:entry_id", delete(delete_balance_entry))
+        .with_state(pool)
+}
+
+/// Looks up one of the user's manual accounts.
+async fn manual_account(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let plaid_account_id = sqlx::query_scalar!(
+        "SELECT plaid_account_id FROM accounts WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if plaid_account_id.is_some() {
+        return Err(AppError::BadRequest(
+            "Balances of accounts linked to Plaid come from sync".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn list_balance_entries(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<BalanceEntry>>, AppError> {
+    let entries = sqlx::query_as!(
+        BalanceEntry,
+        "SELECT e.* FROM account_balance_entries e
+         JOIN accounts a ON e.account_id = a.id
+         WHERE e.account_id = $1 AND a.user_id = $2
+         ORDER BY e.date DESC",
+        id,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(entries))
+}
+
+#[derive(Deserialize)]
+struct BalanceRequest {
+    /// Defaults to today.
+    date: Option<NaiveDate>,
+    balance: Decimal,
+    note: Option<String>,
+}
+
+/// Records the account's balance on a date, replacing any entry for that
+/// date, and recomputes the current balance.
+async fn record_balance(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<BalanceRequest>,
+) -> Result<Json<BalanceEntry>, AppError> {
+    manual_account(&pool, user_id, id).await?;
+
+    let entry = sqlx::query_as!(
+        BalanceEntry,
+        "INSERT INTO account_balance_entries (account_id, date, balance, note)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (account_id, date) DO UPDATE SET
+            balance = EXCLUDED.balance,
+            note = EXCLUDED.note
+         RETURNING *",
+        id,
+        payload.date.unwrap_or_else(|| Utc::now().date_naive()),
+        payload.balance,
+        payload.note
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    history::record(
+        &pool,
+        id,
+        entry.date,
+        entry.balance,
+        history::Source::Manual,
+    )
+    .await?;
+    balances::refresh(&pool, id).await?;
+
+    Ok(Json(entry))
+}
+
+async fn delete_balance_entry(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path((id, entry_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<()>, AppError> {
+    manual_account(&pool, user_id, id).await?;
+
+    let date = sqlx::query_scalar!(
+        "DELETE FROM account_balance_entries WHERE id = $1 AND account_id = $2
+         RETURNING date",
+        entry_id,
+        id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    if let Some(date) = date {
+        sqlx::query!(
+            "DELETE FROM account_balance_history
+             WHERE account_id = $1 AND date = $2 AND source = 'manual'",
+            id,
+            date
+        )
+        .execute(&pool)
+        .await?;
+    }
+
+    balances::refresh(&pool, id).await?;
+
+    Ok(Json(()))
+}
@@ -4,11 +4,13 @@ use axum::{
     Json, Router,
 };
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     db::{models::Budget, DbPool},
+    fx::{self, CurrencyAmount, FxRates, MixedTotal},
     utils::{auth::AuthUser, AppError},
 };
 
@@ -59,7 +61,7 @@ async fn get_budget(
 #[derive(Deserialize)]
 struct CreateBudgetRequest {
     category_id: Uuid,
-    amount: f64,
+    amount: Decimal,
     period: String,
     start_date: NaiveDate,
     end_date: Option<NaiveDate>,
@@ -93,7 +95,7 @@ async fn create_budget(
 
 #[derive(Deserialize)]
 struct UpdateBudgetRequest {
-    amount: Option<f64>,
+    amount: Option<Decimal>,
     end_date: Option<NaiveDate>,
 }
 
@@ -154,11 +156,15 @@ async fn delete_budget(
 #[derive(Serialize)]
 struct BudgetPerformance {
     budget: Budget,
-    spent: f64,
-    remaining: f64,
-    percentage: f64,
+    currency: String,
+    spent: Decimal,
+    spent_original: Vec<CurrencyAmount>,
+    remaining: Decimal,
+    percentage: Decimal,
 }
 
+/// Budget amounts are in the user's reporting currency; spending in other
+/// currencies is converted at each transaction date's rate.
 async fn budget_performance(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
@@ -178,34 +184,47 @@ async fn budget_performance(
         .end_date
         .unwrap_or_else(|| chrono::Local::now().date_naive());
 
-    let spent = sqlx::query_scalar!(
-        "SELECT COALESCE(SUM(ABS(t.amount)), 0) as spent
-         FROM transactions t
+    let reporting_currency = fx::reporting_currency(&pool, user_id).await?;
+
+    let spending = sqlx::query!(
+        "SELECT a.currency, t.date as \"date!\", SUM(ABS(t.amount)) as \"spent!\"
+         FROM transaction_allocations t
          JOIN accounts a ON t.account_id = a.id
          WHERE a.user_id = $1 
          AND t.category_id = $2
          AND t.date >= $3 
          AND t.date <= $4
-         AND t.amount < 0",
+         AND t.amount < 0
+         GROUP BY a.currency, t.date",
         user_id,
         budget.category_id,
         budget.start_date,
         end_date
     )
-    .fetch_one(&pool)
-    .await?
-    .unwrap_or(0.0);
+    .fetch_all(&pool)
+    .await?;
+
+    let currencies = fx::currency_set(spending.iter().map(|s| &s.currency), &reporting_currency);
+
+    let rates = FxRates::load(&pool, &currencies, budget.start_date, end_date).await?;
+
+    let mut spent = MixedTotal::default();
+    for s in spending {
+        spent.add(&rates, s.spent, &s.currency, &reporting_currency, s.date)?;
+    }
 
-    let remaining = budget.amount - spent;
-    let percentage = if budget.amount > 0.0 {
-        (spent / budget.amount) * 100.0
+    let remaining = budget.amount - spent.converted;
+    let percentage = if budget.amount > Decimal::ZERO {
+        (spent.converted / budget.amount * Decimal::ONE_HUNDRED).round_dp(2)
     } else {
-        0.0
+        Decimal::ZERO
     };
 
     Ok(Json(BudgetPerformance {
         budget,
-        spent,
+        currency: reporting_currency,
+        spent: spent.converted,
+        spent_original: spent.original(),
         remaining,
         percentage,
     }))
@@ -0,0 +1,199 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::format::{Item, StrftimeItems};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    db::{models::ImportProfile, DbPool},
+    imports::{self, ImportSummary, StatementFormat},
+    utils::{auth::AuthUser, AppError},
+};
+
+/// Mounted under `/api/accounts`.
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route("/:id/import", post(import_statement))
+        .route(
+            "/:id/import-profile",
+            get(get_import_profile).put(save_import_profile),
+        )
+        .with_state(pool)
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    format: Option<StatementFormat>,
+    #[serde(default)]
+    commit: bool,
+}
+
+/// Takes the statement file as the request body. Previews by default; pass
+/// `commit=true` to add the transactions.
+async fn import_statement(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportSummary>, AppError> {
+    // Older OFX files are often Latin-1; stray bytes only affect descriptions.
+    let content = String::from_utf8_lossy(&body);
+
+    let summary = imports::import(&pool, user_id, id, &content, query.format, query.commit).await?;
+
+    Ok(Json(summary))
+}
+
+async fn get_import_profile(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ImportProfile>, AppError> {
+    let profile = sqlx::query_as!(
+        ImportProfile,
+        "SELECT p.* FROM import_profiles p
+         JOIN accounts a ON p.account_id = a.id
+         WHERE p.account_id = $1 AND a.user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(profile))
+}
+
+#[derive(Deserialize)]
+struct ImportProfileRequest {
+    #[serde(default = "default_delimiter")]
+    delimiter: String,
+    #[serde(default = "default_has_header")]
+    has_header: bool,
+    date_column: i32,
+    #[serde(default = "default_date_format")]
+    date_format: String,
+    description_column: i32,
+    amount_column: Option<i32>,
+    debit_column: Option<i32>,
+    credit_column: Option<i32>,
+    #[serde(default)]
+    negate_amounts: bool,
+    #[serde(default)]
+    decimal_comma: bool,
+}
+
+fn default_delimiter() -> String {
+    ",".to_string()
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+impl ImportProfileRequest {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.delimiter.chars().count() != 1 || self.delimiter == "\"" {
+            return Err(AppError::BadRequest(
+                "The delimiter must be a single character other than a quote".to_string(),
+            ));
+        }
+
+        let columns = [
+            Some(self.date_column),
+            Some(self.description_column),
+            self.amount_column,
+            self.debit_column,
+            self.credit_column,
+        ];
+        if columns.iter().flatten().any(|c| *c < 0) {
+            return Err(AppError::BadRequest(
+                "Columns are numbered from 0".to_string(),
+            ));
+        }
+
+        if self.amount_column.is_none()
+            && self.debit_column.is_none()
+            && self.credit_column.is_none()
+        {
+            return Err(AppError::BadRequest(
+                "Map either an amount column or debit and credit columns".to_string(),
+            ));
+        }
+
+        if self.date_format.is_empty()
+            || StrftimeItems::new(&self.date_format).any(|item| matches!(item, Item::Error))
+        {
+            return Err(AppError::BadRequest(format!(
+                "Invalid date format {:?}",
+                self.date_format
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates or replaces the account's CSV column mapping.
+async fn save_import_profile(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ImportProfileRequest>,
+) -> Result<Json<ImportProfile>, AppError> {
+    payload.validate()?;
+
+    sqlx::query_scalar!(
+        "SELECT id FROM accounts WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let profile = sqlx::query_as!(
+        ImportProfile,
+        "INSERT INTO import_profiles
+            (account_id, delimiter, has_header, date_column, date_format, description_column,
+             amount_column, debit_column, credit_column, negate_amounts, decimal_comma)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         ON CONFLICT (account_id) DO UPDATE SET
+            delimiter = EXCLUDED.delimiter,
+            has_header = EXCLUDED.has_header,
+            date_column = EXCLUDED.date_column,
+            date_format = EXCLUDED.date_format,
+            description_column = EXCLUDED.description_column,
+            amount_column = EXCLUDED.amount_column,
+            debit_column = EXCLUDED.debit_column,
+            credit_column = EXCLUDED.credit_column,
+            negate_amounts = EXCLUDED.negate_amounts,
+            decimal_comma = EXCLUDED.decimal_comma,
+            updated_at = NOW()
+         RETURNING *",
+        id,
+        payload.delimiter,
+        payload.has_header,
+        payload.date_column,
+        payload.date_format,
+        payload.description_column,
+        payload.amount_column,
+        payload.debit_column,
+        payload.credit_column,
+        payload.negate_amounts,
+        payload.decimal_comma
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(profile))
+}
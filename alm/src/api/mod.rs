@@ -1,6 +1,13 @@
 pub mod accounts;
 pub mod analytics;
 pub mod auth;
+pub mod balances;
 pub mod budgets;
 pub mod categories;
+pub mod imports;
+pub mod plaid;
+pub mod rules;
+pub mod splits;
 pub mod transactions;
+pub mod transfers;
+pub mod users;
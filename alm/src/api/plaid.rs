@@ -0,0 +1,347 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    balances,
+    db::{models::PlaidItem, DbPool},
+    plaid::{
+        link::{self, LinkedItem},
+        sync::{self, SyncSummary},
+        tokens,
+        webhook::{self, WebhookPayload, WebhookVerifier},
+        PlaidClient,
+    },
+    utils::{auth::AuthUser, crypto::KeyRing, AppError},
+};
+
+#[derive(Clone)]
+struct PlaidState {
+    pool: DbPool,
+    plaid: PlaidClient,
+    keys: Arc<KeyRing>,
+    verifier: WebhookVerifier,
+}
+
+pub fn routes(pool: DbPool, plaid: PlaidClient, keys: Arc<KeyRing>) -> Router {
+    let state = PlaidState {
+        pool,
+        keys,
+        verifier: WebhookVerifier::new(plaid.clone()),
+        plaid,
+    };
+
+    Router::new()
+        .route("/link-token", post(create_link_token))
+        .route("/exchange-token", post(exchange_token))
+        .route("/sync", post(sync_items))
+        .route("/items", get(list_items))
+        .route("/items/:id", delete(remove_item))
+        .route("/items/:id/link-token", post(create_update_link_token))
+        .route("/items/:id/reconnect", post(reconnect_item))
+        .route("/webhook", post(receive_webhook))
+        .with_state(state)
+}
+
+#[derive(Serialize)]
+struct LinkTokenResponse {
+    link_token: String,
+}
+
+async fn create_link_token(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+) -> Result<Json<LinkTokenResponse>, AppError> {
+    let link_token = state.plaid.create_link_token(user_id.to_string()).await?;
+
+    Ok(Json(LinkTokenResponse { link_token }))
+}
+
+#[derive(Deserialize)]
+struct ExchangeTokenRequest {
+    public_token: String,
+}
+
+async fn exchange_token(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+    Json(payload): Json<ExchangeTokenRequest>,
+) -> Result<Json<LinkedItem>, AppError> {
+    let linked = link::exchange(
+        &state.pool,
+        &state.plaid,
+        &state.keys,
+        user_id,
+        &payload.public_token,
+    )
+    .await?;
+
+    Ok(Json(linked))
+}
+
+#[derive(Serialize)]
+struct ItemSyncResult {
+    item_id: Uuid,
+    summary: SyncSummary,
+}
+
+async fn sync_items(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+) -> Result<Json<Vec<ItemSyncResult>>, AppError> {
+    let item_ids = sqlx::query_scalar!(
+        "SELECT id FROM plaid_items WHERE user_id = $1 AND status IN ('active', 'pending_expiration') ORDER BY created_at",
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut results = Vec::with_capacity(item_ids.len());
+
+    for item_id in item_ids {
+        let summary = sync::sync_item(&state.pool, &state.plaid, &state.keys, item_id).await?;
+        results.push(ItemSyncResult { item_id, summary });
+    }
+
+    Ok(Json(results))
+}
+
+#[derive(Serialize)]
+struct PlaidItemSummary {
+    id: Uuid,
+    plaid_item_id: String,
+    institution_id: String,
+    institution_name: String,
+    status: String,
+    created_at: DateTime<Utc>,
+}
+
+async fn list_items(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+) -> Result<Json<Vec<PlaidItemSummary>>, AppError> {
+    let items = sqlx::query_as!(
+        PlaidItemSummary,
+        "SELECT id, plaid_item_id, institution_id, institution_name, status, created_at
+         FROM plaid_items WHERE user_id = $1 ORDER BY created_at",
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(items))
+}
+
+/// Issues a Link token in update mode so the user can repair a broken login
+/// without relinking, which would orphan the item's accounts and history.
+async fn create_update_link_token(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LinkTokenResponse>, AppError> {
+    let item = sqlx::query_as!(
+        PlaidItem,
+        "SELECT * FROM plaid_items WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let access_token = tokens::open_access_token(&state.keys, &item)?;
+
+    let link_token = state
+        .plaid
+        .create_update_link_token(user_id.to_string(), &access_token)
+        .await?;
+
+    Ok(Json(LinkTokenResponse { link_token }))
+}
+
+/// Called once the user finishes Link update mode. The item keeps its access
+/// token and accounts, so it only needs reactivating and a fresh sync; if the
+/// login is still broken the sync flags it again.
+async fn reconnect_item(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SyncSummary>, AppError> {
+    let result = sqlx::query!(
+        "UPDATE plaid_items SET status = 'active' WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&state.pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let summary = sync::sync_item(&state.pool, &state.plaid, &state.keys, id).await?;
+
+    Ok(Json(summary))
+}
+
+/// What happens to an item's transaction history when it is removed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TransactionRetention {
+    /// Archive the item's accounts, transactions included. Archived accounts
+    /// become manual ones and drop out of net worth.
+    #[default]
+    Keep,
+    /// Move the transactions into an existing manual account and delete the
+    /// item's accounts.
+    Detach,
+    /// Delete the item's accounts and all of their transactions.
+    Purge,
+}
+
+#[derive(Deserialize)]
+struct RemoveItemQuery {
+    #[serde(default)]
+    transactions: TransactionRetention,
+    account_id: Option<Uuid>,
+}
+
+/// Revokes the item with Plaid so it stops being billed, then removes it
+/// locally. Any detach target is checked first, since the Plaid side cannot
+/// be undone.
+async fn remove_item(
+    AuthUser { user_id }: AuthUser,
+    State(state): State<PlaidState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<RemoveItemQuery>,
+) -> Result<Json<()>, AppError> {
+    let item = sqlx::query_as!(
+        PlaidItem,
+        "SELECT * FROM plaid_items WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let target_account_id = match query.transactions {
+        TransactionRetention::Detach => {
+            let account_id = query.account_id.ok_or(AppError::BadRequest(
+                "account_id is required to detach transactions".to_string(),
+            ))?;
+
+            sqlx::query_scalar!(
+                "SELECT id FROM accounts
+                 WHERE id = $1 AND user_id = $2 AND plaid_item_id IS NULL AND archived_at IS NULL",
+                account_id,
+                user_id
+            )
+            .fetch_optional(&state.pool)
+            .await?
+            .ok_or(AppError::BadRequest(
+                "Transactions can only be detached to an active manual account".to_string(),
+            ))?;
+
+            Some(account_id)
+        }
+        _ => None,
+    };
+
+    let access_token = tokens::open_access_token(&state.keys, &item)?;
+    state.plaid.remove_item(&access_token).await?;
+
+    let mut tx = state.pool.begin().await?;
+
+    match query.transactions {
+        TransactionRetention::Keep => {
+            sqlx::query!(
+                "UPDATE accounts SET plaid_item_id = NULL, plaid_account_id = NULL, archived_at = NOW()
+                 WHERE user_id = $1 AND plaid_item_id = $2",
+                user_id,
+                item.plaid_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        TransactionRetention::Detach => {
+            // Detached rows no longer belong to Plaid, so drop their ids to keep
+            // a future link from syncing into the manual account.
+            sqlx::query!(
+                "UPDATE transactions t SET account_id = $1, plaid_transaction_id = NULL, updated_at = NOW()
+                 FROM accounts a
+                 WHERE t.account_id = a.id AND a.user_id = $2 AND a.plaid_item_id = $3",
+                target_account_id,
+                user_id,
+                item.plaid_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                "DELETE FROM accounts WHERE user_id = $1 AND plaid_item_id = $2",
+                user_id,
+                item.plaid_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        TransactionRetention::Purge => {
+            sqlx::query!(
+                "DELETE FROM accounts WHERE user_id = $1 AND plaid_item_id = $2",
+                user_id,
+                item.plaid_item_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query!("DELETE FROM plaid_items WHERE id = $1", item.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    if let Some(account_id) = target_account_id {
+        balances::refresh(&state.pool, account_id).await?;
+    }
+
+    Ok(Json(()))
+}
+
+/// Plaid expects a quick 200, so the webhook is verified inline and the
+/// resulting sync or status change runs in the background.
+async fn receive_webhook(
+    State(state): State<PlaidState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<()>, AppError> {
+    let token = headers
+        .get("Plaid-Verification")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    state.verifier.verify(token, &body).await?;
+
+    let payload: WebhookPayload =
+        serde_json::from_slice(&body).map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    tokio::spawn(async move {
+        if let Err(e) =
+            webhook::handle_webhook(&state.pool, &state.plaid, &state.keys, payload).await
+        {
+            tracing::warn!("Failed to handle Plaid webhook: {:?}", e);
+        }
+    });
+
+    Ok(Json(()))
+}
@@ -0,0 +1,389 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    db::{models::CategorizationRule, DbPool},
+    rules::{CompiledRule, RuleInput},
+    utils::{auth::AuthUser, AppError},
+};
+
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/:id", get(get_rule).put(update_rule).delete(delete_rule))
+        .route("/:id/preview", get(preview_rule))
+        .route("/:id/apply", post(apply_rule))
+        .with_state(pool)
+}
+
+async fn list_rules(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+) -> Result<Json<Vec<CategorizationRule>>, AppError> {
+    let rules = sqlx::query_as!(
+        CategorizationRule,
+        "SELECT * FROM categorization_rules WHERE user_id = $1 ORDER BY priority DESC, created_at",
+        user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(rules))
+}
+
+async fn get_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CategorizationRule>, AppError> {
+    Ok(Json(load_rule(&pool, user_id, id).await?))
+}
+
+/// Used for both create and update; an update replaces the whole rule.
+#[derive(Deserialize)]
+struct RuleRequest {
+    name: String,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default = "default_match_type")]
+    match_type: String,
+    merchant_pattern: Option<String>,
+    description_pattern: Option<String>,
+    min_amount: Option<Decimal>,
+    max_amount: Option<Decimal>,
+    account_id: Option<Uuid>,
+    set_category_id: Option<Uuid>,
+    set_description: Option<String>,
+    #[serde(default = "default_active")]
+    is_active: bool,
+}
+
+fn default_match_type() -> String {
+    "contains".to_string()
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl RuleRequest {
+    /// Checks the rule is well formed and only refers to the user's own
+    /// accounts and categories.
+    async fn validate(&mut self, pool: &DbPool, user_id: Uuid) -> Result<(), AppError> {
+        fn blank_to_none(value: &mut Option<String>) {
+            if value.as_deref().is_some_and(|v| v.trim().is_empty()) {
+                *value = None;
+            }
+        }
+
+        blank_to_none(&mut self.merchant_pattern);
+        blank_to_none(&mut self.description_pattern);
+        blank_to_none(&mut self.set_description);
+
+        if self.name.trim().is_empty() {
+            return Err(AppError::BadRequest("Rule name is required".to_string()));
+        }
+
+        if self.merchant_pattern.is_none()
+            && self.description_pattern.is_none()
+            && self.min_amount.is_none()
+            && self.max_amount.is_none()
+            && self.account_id.is_none()
+        {
+            return Err(AppError::BadRequest(
+                "A rule needs at least one condition".to_string(),
+            ));
+        }
+
+        if self.set_category_id.is_none() && self.set_description.is_none() {
+            return Err(AppError::BadRequest(
+                "A rule must set a category or a description".to_string(),
+            ));
+        }
+
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount) {
+            if min > max {
+                return Err(AppError::BadRequest(
+                    "min_amount must not exceed max_amount".to_string(),
+                ));
+            }
+        }
+
+        // Compiling catches bad regexes and unknown match types up front.
+        CompiledRule::compile(self.to_rule(Uuid::nil(), user_id))?;
+
+        if let Some(account_id) = self.account_id {
+            sqlx::query_scalar!(
+                "SELECT id FROM accounts WHERE id = $1 AND user_id = $2",
+                account_id,
+                user_id
+            )
+            .fetch_optional(pool)
+            .await?
+            .ok_or(AppError::BadRequest("Invalid account".to_string()))?;
+        }
+
+        if let Some(category_id) = self.set_category_id {
+            sqlx::query_scalar!(
+                "SELECT id FROM categories WHERE id = $1 AND (user_id = $2 OR is_default = true)",
+                category_id,
+                user_id
+            )
+            .fetch_optional(pool)
+            .await?
+            .ok_or(AppError::BadRequest("Invalid category".to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    fn to_rule(&self, id: Uuid, user_id: Uuid) -> CategorizationRule {
+        let now = chrono::Utc::now();
+
+        CategorizationRule {
+            id,
+            user_id,
+            name: self.name.trim().to_string(),
+            priority: self.priority,
+            match_type: self.match_type.clone(),
+            merchant_pattern: self.merchant_pattern.clone(),
+            description_pattern: self.description_pattern.clone(),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            account_id: self.account_id,
+            set_category_id: self.set_category_id,
+            set_description: self.set_description.clone(),
+            is_active: self.is_active,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+async fn create_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(mut payload): Json<RuleRequest>,
+) -> Result<Json<CategorizationRule>, AppError> {
+    payload.validate(&pool, user_id).await?;
+
+    let rule = sqlx::query_as!(
+        CategorizationRule,
+        "INSERT INTO categorization_rules
+            (id, user_id, name, priority, match_type, merchant_pattern, description_pattern,
+             min_amount, max_amount, account_id, set_category_id, set_description, is_active)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+         RETURNING *",
+        Uuid::new_v4(),
+        user_id,
+        payload.name.trim(),
+        payload.priority,
+        payload.match_type,
+        payload.merchant_pattern,
+        payload.description_pattern,
+        payload.min_amount,
+        payload.max_amount,
+        payload.account_id,
+        payload.set_category_id,
+        payload.set_description,
+        payload.is_active
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(rule))
+}
+
+async fn update_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(mut payload): Json<RuleRequest>,
+) -> Result<Json<CategorizationRule>, AppError> {
+    payload.validate(&pool, user_id).await?;
+
+    let rule = sqlx::query_as!(
+        CategorizationRule,
+        "UPDATE categorization_rules SET
+            name = $3,
+            priority = $4,
+            match_type = $5,
+            merchant_pattern = $6,
+            description_pattern = $7,
+            min_amount = $8,
+            max_amount = $9,
+            account_id = $10,
+            set_category_id = $11,
+            set_description = $12,
+            is_active = $13,
+            updated_at = NOW()
+         WHERE id = $1 AND user_id = $2
+         RETURNING *",
+        id,
+        user_id,
+        payload.name.trim(),
+        payload.priority,
+        payload.match_type,
+        payload.merchant_pattern,
+        payload.description_pattern,
+        payload.min_amount,
+        payload.max_amount,
+        payload.account_id,
+        payload.set_category_id,
+        payload.set_description,
+        payload.is_active
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(rule))
+}
+
+async fn delete_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<()>, AppError> {
+    sqlx::query!(
+        "DELETE FROM categorization_rules WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(()))
+}
+
+/// An existing transaction the rule would change.
+#[derive(Serialize)]
+struct RuleMatch {
+    transaction_id: Uuid,
+    date: NaiveDate,
+    amount: Decimal,
+    merchant_name: Option<String>,
+    description: String,
+    category_id: Option<Uuid>,
+    new_description: String,
+    new_category_id: Option<Uuid>,
+}
+
+async fn preview_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<RuleMatch>>, AppError> {
+    let rule = CompiledRule::compile(load_rule(&pool, user_id, id).await?)?;
+
+    Ok(Json(find_matches(&pool, user_id, &rule).await?))
+}
+
+#[derive(Serialize)]
+struct ApplyRuleResponse {
+    updated: usize,
+}
+
+/// Applies a single rule to existing transactions, regardless of priority or
+/// whether it is active.
+async fn apply_rule(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApplyRuleResponse>, AppError> {
+    let rule = CompiledRule::compile(load_rule(&pool, user_id, id).await?)?;
+    let ids: Vec<Uuid> = find_matches(&pool, user_id, &rule)
+        .await?
+        .into_iter()
+        .map(|m| m.transaction_id)
+        .collect();
+
+    sqlx::query!(
+        "UPDATE transactions SET
+            category_id = COALESCE($2, category_id),
+            description = COALESCE($3, description),
+            updated_at = NOW()
+         WHERE id = ANY($1)",
+        &ids,
+        rule.rule().set_category_id,
+        rule.rule().set_description
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(ApplyRuleResponse { updated: ids.len() }))
+}
+
+async fn load_rule(pool: &DbPool, user_id: Uuid, id: Uuid) -> Result<CategorizationRule, AppError> {
+    sqlx::query_as!(
+        CategorizationRule,
+        "SELECT * FROM categorization_rules WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// Account and amount conditions narrow the scan in SQL; text patterns are
+/// matched here so previews agree exactly with how imports are categorized.
+async fn find_matches(
+    pool: &DbPool,
+    user_id: Uuid,
+    rule: &CompiledRule,
+) -> Result<Vec<RuleMatch>, AppError> {
+    let r = rule.rule();
+
+    let candidates = sqlx::query!(
+        "SELECT t.id, t.account_id, t.date, t.amount, t.description, t.merchant_name, t.category_id
+         FROM transactions t
+         JOIN accounts a ON t.account_id = a.id
+         WHERE a.user_id = $1
+         AND ($2::uuid IS NULL OR t.account_id = $2)
+         AND ($3::numeric IS NULL OR ABS(t.amount) >= $3)
+         AND ($4::numeric IS NULL OR ABS(t.amount) <= $4)
+         ORDER BY t.date DESC, t.id DESC",
+        user_id,
+        r.account_id,
+        r.min_amount,
+        r.max_amount
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let matches = candidates
+        .into_iter()
+        .filter(|t| {
+            rule.matches(&RuleInput {
+                account_id: t.account_id,
+                description: &t.description,
+                merchant_name: t.merchant_name.as_deref(),
+                amount: t.amount,
+            })
+        })
+        .map(|t| RuleMatch {
+            transaction_id: t.id,
+            date: t.date,
+            amount: t.amount,
+            new_description: r
+                .set_description
+                .clone()
+                .unwrap_or_else(|| t.description.clone()),
+            new_category_id: r.set_category_id.or(t.category_id),
+            merchant_name: t.merchant_name,
+            description: t.description,
+            category_id: t.category_id,
+        })
+        .filter(|m| m.new_description != m.description || m.new_category_id != m.category_id)
+        .collect();
+
+    Ok(matches)
+}
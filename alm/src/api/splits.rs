@@ -0,0 +1,238 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, patch},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::transactions::{check_category, nullable};
+use crate::{
+    db::{models::TransactionSplit, DbPool},
+    utils::{auth::AuthUser, AppError},
+};
+
+/// Most allocations a single transaction can be split into.
+const MAX_SPLITS: usize = 50;
+
+/// Mounted under `/api/transactions`.
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route(
+            "/:id/splits",
+            get(list_splits).put(replace_splits).delete(delete_splits),
+        )
+        .route("/:id/splits/:split_id", patch(update_split))
+        .with_state(pool)
+}
+
+async fn list_splits(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<TransactionSplit>>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT t.id FROM transactions t
+         JOIN accounts a ON t.account_id = a.id
+         WHERE t.id = $1 AND a.user_id = $2",
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let splits = sqlx::query_as!(
+        TransactionSplit,
+        "SELECT * FROM transaction_splits WHERE transaction_id = $1 ORDER BY created_at, id",
+        id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(splits))
+}
+
+#[derive(Deserialize)]
+struct SplitRequest {
+    category_id: Option<Uuid>,
+    amount: Decimal,
+    memo: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReplaceSplitsRequest {
+    splits: Vec<SplitRequest>,
+}
+
+/// Splits share the parent's sign, are whole cents and add up to exactly
+/// its amount.
+fn validate_splits(amount: Decimal, splits: &[SplitRequest]) -> Result<(), AppError> {
+    if splits.is_empty() || splits.len() > MAX_SPLITS {
+        return Err(AppError::BadRequest(format!(
+            "A transaction can be split into 1 to {} parts",
+            MAX_SPLITS
+        )));
+    }
+
+    if amount.is_zero() {
+        return Err(AppError::BadRequest(
+            "A zero-amount transaction cannot be split".to_string(),
+        ));
+    }
+
+    if splits
+        .iter()
+        .any(|s| s.amount.is_zero() || s.amount.is_sign_negative() != amount.is_sign_negative())
+    {
+        return Err(AppError::BadRequest(
+            "Split amounts must be non-zero and have the same sign as the transaction".to_string(),
+        ));
+    }
+
+    // Amounts are stored to the cent; rounding finer ones on insert would
+    // break the sum checked below.
+    if splits.iter().any(|s| s.amount.normalize().scale() > 2) {
+        return Err(AppError::BadRequest(
+            "Split amounts can have at most two decimal places".to_string(),
+        ));
+    }
+
+    let total: Decimal = splits.iter().map(|s| s.amount).sum();
+    if total != amount {
+        return Err(AppError::BadRequest(format!(
+            "Splits add up to {} but the transaction amount is {}",
+            total, amount
+        )));
+    }
+
+    Ok(())
+}
+
+/// Replaces all of a transaction's splits at once, so they always add up to
+/// its amount.
+async fn replace_splits(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ReplaceSplitsRequest>,
+) -> Result<Json<Vec<TransactionSplit>>, AppError> {
+    for category_id in payload.splits.iter().filter_map(|s| s.category_id) {
+        check_category(&pool, user_id, category_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let amount = sqlx::query_scalar!(
+        "SELECT t.amount FROM transactions t
+         JOIN accounts a ON t.account_id = a.id
+         WHERE t.id = $1 AND a.user_id = $2
+         FOR UPDATE OF t",
+        id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    validate_splits(amount, &payload.splits)?;
+
+    sqlx::query!(
+        "DELETE FROM transaction_splits WHERE transaction_id = $1",
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let ids: Vec<Uuid> = payload.splits.iter().map(|_| Uuid::new_v4()).collect();
+    let category_ids: Vec<Option<Uuid>> = payload.splits.iter().map(|s| s.category_id).collect();
+    let amounts: Vec<Decimal> = payload.splits.iter().map(|s| s.amount).collect();
+    let memos: Vec<Option<String>> = payload.splits.into_iter().map(|s| s.memo).collect();
+
+    let splits = sqlx::query_as!(
+        TransactionSplit,
+        "INSERT INTO transaction_splits (id, transaction_id, category_id, amount, memo)
+         SELECT s.id, $1, s.category_id, s.amount, s.memo
+         FROM UNNEST($2::uuid[], $3::uuid[], $4::numeric[], $5::text[])
+            AS s(id, category_id, amount, memo)
+         RETURNING *",
+        id,
+        &ids,
+        &category_ids as &[Option<Uuid>],
+        &amounts,
+        &memos as &[Option<String>]
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(splits))
+}
+
+/// Removes all splits, so the transaction counts towards its own category
+/// again.
+async fn delete_splits(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<()>, AppError> {
+    sqlx::query!(
+        "DELETE FROM transaction_splits s
+         USING transactions t, accounts a
+         WHERE s.transaction_id = t.id AND t.account_id = a.id
+         AND t.id = $1 AND a.user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(()))
+}
+
+/// Changes a split's category or memo. Amounts change through `PUT`, which
+/// rebalances the whole set.
+#[derive(Deserialize)]
+struct UpdateSplitRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    category_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "nullable")]
+    memo: Option<Option<String>>,
+}
+
+async fn update_split(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path((id, split_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateSplitRequest>,
+) -> Result<Json<TransactionSplit>, AppError> {
+    if let Some(Some(category_id)) = payload.category_id {
+        check_category(&pool, user_id, category_id).await?;
+    }
+
+    let split = sqlx::query_as!(
+        TransactionSplit,
+        "UPDATE transaction_splits s SET
+            category_id = CASE WHEN $4 THEN $5 ELSE s.category_id END,
+            memo = CASE WHEN $6 THEN $7 ELSE s.memo END,
+            updated_at = NOW()
+         FROM transactions t, accounts a
+         WHERE s.transaction_id = t.id AND t.account_id = a.id
+         AND s.id = $1 AND t.id = $2 AND a.user_id = $3
+         RETURNING s.*",
+        split_id,
+        id,
+        user_id,
+        payload.category_id.is_some(),
+        payload.category_id.flatten(),
+        payload.memo.is_some(),
+        payload.memo.flatten()
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(split))
+}
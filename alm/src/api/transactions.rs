@@ -1,95 +1,362 @@
 use axum::{
     extract::{Path, Query, State},
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{postgres::PgArguments, query::QueryAs, Postgres};
+use std::collections::HashSet;
 use uuid::Uuid;
 
 use crate::{
+    balances,
     db::{models::Transaction, DbPool},
+    duplicates::{self, DuplicatePair},
+    rules::{
+        suggestions::{Suggestion, SuggestionModel},
+        RuleInput, RuleSet,
+    },
+    transfers,
     utils::{auth::AuthUser, AppError},
 };
 
 pub fn routes(pool: DbPool) -> Router {
     Router::new()
         .route("/", get(list_transactions).post(create_transaction))
+        .route("/bulk", patch(bulk_update_transactions))
+        .route("/duplicates", get(list_duplicates))
+        .route("/suggestions/accept", post(accept_suggestions))
         .route(
             "/:id",
             get(get_transaction)
                 .put(update_transaction)
                 .delete(delete_transaction),
         )
-        .with_state(pool)
+        .route("/:id/suggestions", get(suggest_categories))
+        .route("/:id/merge", post(merge_transactions))
+        .with_state(pool.clone())
+        .merge(super::splits::routes(pool))
 }
 
-#[derive(Deserialize)]
+/// Page size when the client does not ask for one, and the most it may ask for.
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// The text searched by `q`. It must match the expression behind
+/// `idx_transactions_search` for the index to be used.
+const SEARCH_DOCUMENT: &str =
+    "to_tsvector('english', t.description || ' ' || COALESCE(t.merchant_name, ''))";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TransactionSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    AmountDesc,
+    AmountAsc,
+}
+
+impl TransactionSort {
+    fn by_date(self) -> bool {
+        matches!(self, TransactionSort::DateDesc | TransactionSort::DateAsc)
+    }
+
+    fn column(self) -> &'static str {
+        if self.by_date() {
+            "t.date"
+        } else {
+            "t.amount"
+        }
+    }
+
+    fn is_descending(self) -> bool {
+        matches!(
+            self,
+            TransactionSort::DateDesc | TransactionSort::AmountDesc
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TransactionKind {
+    Income,
+    Expense,
+}
+
+#[derive(Default, Deserialize)]
 struct TransactionQuery {
     account_id: Option<Uuid>,
     category_id: Option<Uuid>,
     start_date: Option<NaiveDate>,
     end_date: Option<NaiveDate>,
     uncategorized: Option<bool>,
+    /// Full-text search over description and merchant, in web search syntax.
+    q: Option<String>,
+    merchant: Option<String>,
+    /// Bounds on the absolute amount, so they read the same for spending
+    /// (stored negative) and income.
+    min_amount: Option<Decimal>,
+    max_amount: Option<Decimal>,
+    pending: Option<bool>,
+    kind: Option<TransactionKind>,
+    #[serde(default)]
+    sort: TransactionSort,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Position after the last row of a page: its sort value and id, the id
+/// breaking ties between rows with the same date or amount.
+enum Cursor {
+    Date(NaiveDate, Uuid),
+    Amount(Decimal, Uuid),
+}
+
+impl Cursor {
+    fn after(sort: TransactionSort, transaction: &Transaction) -> Self {
+        if sort.by_date() {
+            Cursor::Date(transaction.date, transaction.id)
+        } else {
+            Cursor::Amount(transaction.amount, transaction.id)
+        }
+    }
+
+    fn encode(&self) -> String {
+        let raw = match self {
+            Cursor::Date(date, id) => format!("{}|{}", date, id),
+            Cursor::Amount(amount, id) => format!("{}|{}", amount, id),
+        };
+
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    fn decode(sort: TransactionSort, encoded: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::BadRequest("Invalid cursor".to_string());
+
+        let raw = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let (value, id) = raw.split_once('|').ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        if sort.by_date() {
+            Ok(Cursor::Date(value.parse().map_err(|_| invalid())?, id))
+        } else {
+            Ok(Cursor::Amount(value.parse().map_err(|_| invalid())?, id))
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TransactionPage {
+    transactions: Vec<Transaction>,
+    next_cursor: Option<String>,
+}
+
+impl TransactionQuery {
+    fn search(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    /// An ILIKE pattern for `merchant`, with LIKE wildcards escaped so the
+    /// name matches literally.
+    fn merchant_pattern(&self) -> Option<String> {
+        self.merchant
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                format!(
+                    "%{}%",
+                    m.replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                )
+            })
+    }
+
+    /// Appends this query's filters to `sql`, numbering placeholders after
+    /// `param_count`, and returns the new count. `bind_filters` must bind the
+    /// values in the same order.
+    fn push_filters(&self, sql: &mut String, mut param_count: usize) -> usize {
+        if self.account_id.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.account_id = ${}", param_count));
+        }
+
+        if self.category_id.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.category_id = ${}", param_count));
+        }
+
+        if self.uncategorized == Some(true) {
+            sql.push_str(" AND t.category_id IS NULL");
+        }
+
+        if self.start_date.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.date >= ${}", param_count));
+        }
+
+        if self.end_date.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.date <= ${}", param_count));
+        }
+
+        if self.search().is_some() {
+            param_count += 1;
+            sql.push_str(&format!(
+                " AND {} @@ websearch_to_tsquery('english', ${})",
+                SEARCH_DOCUMENT, param_count
+            ));
+        }
+
+        if self.merchant_pattern().is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.merchant_name ILIKE ${}", param_count));
+        }
+
+        if self.min_amount.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND ABS(t.amount) >= ${}", param_count));
+        }
+
+        if self.max_amount.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND ABS(t.amount) <= ${}", param_count));
+        }
+
+        if self.pending.is_some() {
+            param_count += 1;
+            sql.push_str(&format!(" AND t.pending = ${}", param_count));
+        }
+
+        match self.kind {
+            Some(TransactionKind::Income) => sql.push_str(" AND t.amount > 0"),
+            Some(TransactionKind::Expense) => sql.push_str(" AND t.amount < 0"),
+            None => {}
+        }
+
+        param_count
+    }
+
+    fn bind_filters<'q, O>(
+        &self,
+        mut query_builder: QueryAs<'q, Postgres, O, PgArguments>,
+    ) -> QueryAs<'q, Postgres, O, PgArguments> {
+        if let Some(account_id) = self.account_id {
+            query_builder = query_builder.bind(account_id);
+        }
+        if let Some(category_id) = self.category_id {
+            query_builder = query_builder.bind(category_id);
+        }
+        if let Some(start_date) = self.start_date {
+            query_builder = query_builder.bind(start_date);
+        }
+        if let Some(end_date) = self.end_date {
+            query_builder = query_builder.bind(end_date);
+        }
+        if let Some(search) = self.search() {
+            query_builder = query_builder.bind(search.to_string());
+        }
+        if let Some(merchant) = self.merchant_pattern() {
+            query_builder = query_builder.bind(merchant);
+        }
+        if let Some(min_amount) = self.min_amount {
+            query_builder = query_builder.bind(min_amount);
+        }
+        if let Some(max_amount) = self.max_amount {
+            query_builder = query_builder.bind(max_amount);
+        }
+        if let Some(pending) = self.pending {
+            query_builder = query_builder.bind(pending);
+        }
+
+        query_builder
+    }
 }
 
 async fn list_transactions(
     AuthUser { user_id }: AuthUser,
     State(pool): State<DbPool>,
     Query(query): Query<TransactionQuery>,
-) -> Result<Json<Vec<Transaction>>, AppError> {
+) -> Result<Json<TransactionPage>, AppError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|c| Cursor::decode(query.sort, c))
+        .transpose()?;
+
     let mut sql = String::from(
         "SELECT t.* FROM transactions t 
          JOIN accounts a ON t.account_id = a.id 
          WHERE a.user_id = $1",
     );
 
-    let mut param_count = 1;
+    let mut param_count = query.push_filters(&mut sql, 1);
 
-    if query.account_id.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND t.account_id = ${}", param_count));
-    }
+    let (direction, comparison) = if query.sort.is_descending() {
+        ("DESC", "<")
+    } else {
+        ("ASC", ">")
+    };
 
-    if query.category_id.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND t.category_id = ${}", param_count));
+    if cursor.is_some() {
+        sql.push_str(&format!(
+            " AND ({}, t.id) {} (${}, ${})",
+            query.sort.column(),
+            comparison,
+            param_count + 1,
+            param_count + 2
+        ));
+        param_count += 2;
     }
 
-    if query.uncategorized == Some(true) {
-        sql.push_str(" AND t.category_id IS NULL");
-    }
+    sql.push_str(&format!(
+        " ORDER BY {} {}, t.id {} LIMIT ${}",
+        query.sort.column(),
+        direction,
+        direction,
+        param_count + 1
+    ));
 
-    if query.start_date.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND t.date >= ${}", param_count));
-    }
+    let mut query_builder =
+        query.bind_filters(sqlx::query_as::<_, Transaction>(&sql).bind(user_id));
 
-    if query.end_date.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND t.date <= ${}", param_count));
+    match cursor {
+        Some(Cursor::Date(date, id)) => query_builder = query_builder.bind(date).bind(id),
+        Some(Cursor::Amount(amount, id)) => query_builder = query_builder.bind(amount).bind(id),
+        None => {}
     }
 
-    sql.push_str(" ORDER BY t.date DESC, t.created_at DESC");
-
-    let mut query_builder = sqlx::query_as::<_, Transaction>(&sql).bind(user_id);
-
-    if let Some(account_id) = query.account_id {
-        query_builder = query_builder.bind(account_id);
-    }
-    if let Some(category_id) = query.category_id {
-        query_builder = query_builder.bind(category_id);
-    }
-    if let Some(start_date) = query.start_date {
-        query_builder = query_builder.bind(start_date);
-    }
-    if let Some(end_date) = query.end_date {
-        query_builder = query_builder.bind(end_date);
-    }
+    // One extra row tells us whether there is another page.
+    let mut transactions = query_builder.bind(limit + 1).fetch_all(&pool).await?;
 
-    let transactions = query_builder.fetch_all(&pool).await?;
+    let next_cursor = if transactions.len() as i64 > limit {
+        transactions.truncate(limit as usize);
+        transactions
+            .last()
+            .map(|t| Cursor::after(query.sort, t).encode())
+    } else {
+        None
+    };
 
-    Ok(Json(transactions))
+    Ok(Json(TransactionPage {
+        transactions,
+        next_cursor,
+    }))
 }
 
 async fn get_transaction(
@@ -116,7 +383,7 @@ async fn get_transaction(
 struct CreateTransactionRequest {
     account_id: Uuid,
     date: NaiveDate,
-    amount: f64,
+    amount: Decimal,
     description: String,
     category_id: Option<Uuid>,
     merchant_name: Option<String>,
@@ -136,6 +403,13 @@ async fn create_transaction(
     .await?
     .ok_or(AppError::BadRequest("Invalid account".to_string()))?;
 
+    let outcome = RuleSet::load(&pool, user_id).await?.apply(&RuleInput {
+        account_id: payload.account_id,
+        description: &payload.description,
+        merchant_name: payload.merchant_name.as_deref(),
+        amount: payload.amount,
+    });
+
     let transaction_id = Uuid::new_v4();
 
     let transaction = sqlx::query_as!(
@@ -147,22 +421,42 @@ async fn create_transaction(
         payload.account_id,
         payload.date,
         payload.amount,
-        payload.description,
-        payload.category_id,
+        outcome.description.unwrap_or(payload.description),
+        payload.category_id.or(outcome.category_id),
         payload.merchant_name,
         false
     )
     .fetch_one(&pool)
     .await?;
 
+    balances::refresh(&pool, transaction.account_id).await?;
+
+    // A manual entry may be the other leg of a synced or imported transfer.
+    if let Err(e) = transfers::detect(&pool, user_id).await {
+        tracing::warn!("Transfer detection failed for user {}: {:?}", user_id, e);
+    }
+
     Ok(Json(transaction))
 }
 
+/// Distinguishes a field left out of a request (`None`) from one explicitly
+/// set to null (`Some(None)`), so clients can clear optional fields.
+pub(super) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
 #[derive(Deserialize)]
 struct UpdateTransactionRequest {
-    category_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "nullable")]
+    category_id: Option<Option<Uuid>>,
     description: Option<String>,
-    amount: Option<f64>,
+    amount: Option<Decimal>,
+    #[serde(default, deserialize_with = "nullable")]
+    notes: Option<Option<String>>,
 }
 
 async fn update_transaction(
@@ -171,8 +465,240 @@ async fn update_transaction(
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateTransactionRequest>,
 ) -> Result<Json<Transaction>, AppError> {
-    let existing = sqlx::query!(
-        "SELECT t.id FROM transactions t 
+    if let Some(Some(category_id)) = payload.category_id {
+        check_category(&pool, user_id, category_id).await?;
+    }
+
+    if let Some(amount) = payload.amount {
+        let split_total = sqlx::query_scalar!(
+            "SELECT SUM(s.amount) FROM transaction_splits s
+             JOIN transactions t ON s.transaction_id = t.id
+             JOIN accounts a ON t.account_id = a.id
+             WHERE t.id = $1 AND a.user_id = $2",
+            id,
+            user_id
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        if split_total.is_some_and(|total| total != amount) {
+            return Err(AppError::BadRequest(
+                "Update or remove the transaction's splits before changing its amount".to_string(),
+            ));
+        }
+    }
+
+    let transaction = sqlx::query_as!(
+        Transaction,
+        "UPDATE transactions t SET
+            category_id = CASE WHEN $3 THEN $4 ELSE t.category_id END,
+            description = COALESCE($5, t.description),
+            amount = COALESCE($6, t.amount),
+            notes = CASE WHEN $7 THEN $8 ELSE t.notes END,
+            updated_at = NOW()
+         FROM accounts a
+         WHERE t.account_id = a.id AND t.id = $1 AND a.user_id = $2
+         RETURNING t.*",
+        id,
+        user_id,
+        payload.category_id.is_some(),
+        payload.category_id.flatten(),
+        payload.description,
+        payload.amount,
+        payload.notes.is_some(),
+        payload.notes.flatten()
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if payload.amount.is_some() {
+        balances::refresh(&pool, transaction.account_id).await?;
+    }
+
+    Ok(Json(transaction))
+}
+
+/// Upper bound on explicitly listed ids in one bulk request.
+const MAX_BULK_IDS: usize = 1000;
+
+/// Selects transactions either by id or with the same filters as
+/// `GET /api/transactions` (paging fields are ignored, and at least one
+/// filter must be set), and applies the same changes to all of them.
+#[derive(Deserialize)]
+struct BulkUpdateRequest {
+    ids: Option<Vec<Uuid>>,
+    filter: Option<TransactionQuery>,
+    #[serde(default, deserialize_with = "nullable")]
+    category_id: Option<Option<Uuid>>,
+    description: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    notes: Option<Option<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BulkUpdateStatus {
+    Updated,
+    NotFound,
+}
+
+#[derive(Serialize)]
+struct BulkUpdateResult {
+    id: Uuid,
+    status: BulkUpdateStatus,
+}
+
+#[derive(Serialize)]
+struct BulkUpdateResponse {
+    updated: usize,
+    results: Vec<BulkUpdateResult>,
+}
+
+async fn bulk_update_transactions(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(payload): Json<BulkUpdateRequest>,
+) -> Result<Json<BulkUpdateResponse>, AppError> {
+    if payload.category_id.is_none() && payload.description.is_none() && payload.notes.is_none() {
+        return Err(AppError::BadRequest(
+            "Nothing to update: set category_id, description or notes".to_string(),
+        ));
+    }
+
+    if let Some(Some(category_id)) = payload.category_id {
+        check_category(&pool, user_id, category_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Lock the matching rows up front so the reported results reflect exactly
+    // what the update touched.
+    let (requested, owned) = match (&payload.ids, &payload.filter) {
+        (Some(ids), None) => {
+            if ids.len() > MAX_BULK_IDS {
+                return Err(AppError::BadRequest(format!(
+                    "At most {} ids can be updated at once",
+                    MAX_BULK_IDS
+                )));
+            }
+
+            let owned = sqlx::query_scalar!(
+                "SELECT t.id FROM transactions t
+                 JOIN accounts a ON t.account_id = a.id
+                 WHERE a.user_id = $1 AND t.id = ANY($2)
+                 FOR UPDATE OF t",
+                user_id,
+                ids
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            (ids.clone(), owned)
+        }
+        (None, Some(filter)) => {
+            // An empty filter would match every transaction the user has.
+            let mut filters = String::new();
+            filter.push_filters(&mut filters, 1);
+            if filters.is_empty() {
+                return Err(AppError::BadRequest(
+                    "filter must set at least one criterion".to_string(),
+                ));
+            }
+
+            let sql = format!(
+                "SELECT t.id FROM transactions t
+                 JOIN accounts a ON t.account_id = a.id
+                 WHERE a.user_id = $1{} FOR UPDATE OF t",
+                filters
+            );
+
+            let owned: Vec<Uuid> = filter
+                .bind_filters(sqlx::query_as::<_, (Uuid,)>(&sql).bind(user_id))
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .map(|(id,)| id)
+                .collect();
+
+            (owned.clone(), owned)
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Provide either ids or filter".to_string(),
+            ))
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE transactions SET
+            category_id = CASE WHEN $2 THEN $3 ELSE category_id END,
+            description = COALESCE($4, description),
+            notes = CASE WHEN $5 THEN $6 ELSE notes END,
+            updated_at = NOW()
+         WHERE id = ANY($1)",
+        &owned,
+        payload.category_id.is_some(),
+        payload.category_id.flatten(),
+        payload.description,
+        payload.notes.is_some(),
+        payload.notes.flatten()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let owned: HashSet<Uuid> = owned.into_iter().collect();
+    let results = requested
+        .into_iter()
+        .map(|id| BulkUpdateResult {
+            id,
+            status: if owned.contains(&id) {
+                BulkUpdateStatus::Updated
+            } else {
+                BulkUpdateStatus::NotFound
+            },
+        })
+        .collect();
+
+    Ok(Json(BulkUpdateResponse {
+        updated: owned.len(),
+        results,
+    }))
+}
+
+/// Rejects categories that belong to another user.
+pub(super) async fn check_category(
+    pool: &DbPool,
+    user_id: Uuid,
+    category_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query_scalar!(
+        "SELECT id FROM categories WHERE id = $1 AND (user_id = $2 OR is_default = true)",
+        category_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::BadRequest("Invalid category".to_string()))?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SuggestionsResponse {
+    transaction_id: Uuid,
+    suggestions: Vec<Suggestion>,
+}
+
+async fn suggest_categories(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SuggestionsResponse>, AppError> {
+    let transaction = sqlx::query!(
+        "SELECT t.merchant_name, t.description, t.amount FROM transactions t 
          JOIN accounts a ON t.account_id = a.id 
          WHERE t.id = $1 AND a.user_id = $2",
         id,
@@ -182,41 +708,144 @@ async fn update_transaction(
     .await?
     .ok_or(AppError::NotFound)?;
 
-    if let Some(category_id) = payload.category_id {
-        sqlx::query!(
-            "UPDATE transactions SET category_id = $1, updated_at = NOW() WHERE id = $2",
-            category_id,
-            id
-        )
-        .execute(&pool)
-        .await?;
-    }
+    let model = SuggestionModel::load(&pool, user_id).await?;
 
-    if let Some(description) = payload.description {
-        sqlx::query!(
-            "UPDATE transactions SET description = $1, updated_at = NOW() WHERE id = $2",
-            description,
-            id
-        )
-        .execute(&pool)
-        .await?;
+    Ok(Json(SuggestionsResponse {
+        transaction_id: id,
+        suggestions: model.suggest(
+            transaction.merchant_name.as_deref(),
+            &transaction.description,
+            transaction.amount,
+        ),
+    }))
+}
+
+const DEFAULT_MIN_CONFIDENCE: f64 = 0.8;
+
+/// Accepts the top suggestion for every uncategorized transaction matching
+/// `filter` whose confidence is at least `min_confidence`.
+#[derive(Deserialize)]
+struct AcceptSuggestionsRequest {
+    #[serde(default = "default_min_confidence")]
+    min_confidence: f64,
+    #[serde(default)]
+    filter: TransactionQuery,
+}
+
+fn default_min_confidence() -> f64 {
+    DEFAULT_MIN_CONFIDENCE
+}
+
+#[derive(Serialize)]
+struct AcceptedSuggestion {
+    id: Uuid,
+    category_id: Uuid,
+    confidence: f64,
+}
+
+#[derive(Serialize)]
+struct AcceptSuggestionsResponse {
+    updated: usize,
+    results: Vec<AcceptedSuggestion>,
+}
+
+async fn accept_suggestions(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(mut payload): Json<AcceptSuggestionsRequest>,
+) -> Result<Json<AcceptSuggestionsResponse>, AppError> {
+    if !(payload.min_confidence > 0.0 && payload.min_confidence <= 1.0) {
+        return Err(AppError::BadRequest(
+            "min_confidence must be greater than 0 and at most 1".to_string(),
+        ));
     }
 
-    if let Some(amount) = payload.amount {
-        sqlx::query!(
-            "UPDATE transactions SET amount = $1, updated_at = NOW() WHERE id = $2",
-            amount,
-            id
+    let filter = &mut payload.filter;
+    filter.uncategorized = Some(true);
+    filter.category_id = None;
+
+    let mut sql = String::from(
+        "SELECT t.id, t.merchant_name, t.description, t.amount FROM transactions t 
+         JOIN accounts a ON t.account_id = a.id 
+         WHERE a.user_id = $1",
+    );
+    filter.push_filters(&mut sql, 1);
+
+    let candidates = filter
+        .bind_filters(
+            sqlx::query_as::<_, (Uuid, Option<String>, String, Decimal)>(&sql).bind(user_id),
         )
-        .execute(&pool)
+        .fetch_all(&pool)
         .await?;
-    }
 
-    let transaction = sqlx::query_as!(Transaction, "SELECT * FROM transactions WHERE id = $1", id)
-        .fetch_one(&pool)
-        .await?;
+    let model = SuggestionModel::load(&pool, user_id).await?;
 
-    Ok(Json(transaction))
+    let accepted: Vec<AcceptedSuggestion> = candidates
+        .into_iter()
+        .filter_map(|(id, merchant_name, description, amount)| {
+            let top = model
+                .suggest(merchant_name.as_deref(), &description, amount)
+                .into_iter()
+                .next()?;
+
+            (top.confidence >= payload.min_confidence).then_some(AcceptedSuggestion {
+                id,
+                category_id: top.category_id,
+                confidence: top.confidence,
+            })
+        })
+        .collect();
+
+    let ids: Vec<Uuid> = accepted.iter().map(|a| a.id).collect();
+    let category_ids: Vec<Uuid> = accepted.iter().map(|a| a.category_id).collect();
+
+    // Skip anything categorized since it was read rather than overwrite it.
+    let updated: HashSet<Uuid> = sqlx::query_scalar!(
+        "UPDATE transactions t SET category_id = s.category_id, updated_at = NOW()
+         FROM UNNEST($1::uuid[], $2::uuid[]) AS s(id, category_id)
+         WHERE t.id = s.id AND t.category_id IS NULL
+         RETURNING t.id",
+        &ids,
+        &category_ids
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let results: Vec<AcceptedSuggestion> = accepted
+        .into_iter()
+        .filter(|a| updated.contains(&a.id))
+        .collect();
+
+    Ok(Json(AcceptSuggestionsResponse {
+        updated: results.len(),
+        results,
+    }))
+}
+
+async fn list_duplicates(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+) -> Result<Json<Vec<DuplicatePair>>, AppError> {
+    Ok(Json(duplicates::find(&pool, user_id).await?))
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    duplicate_id: Uuid,
+}
+
+/// Folds `duplicate_id` into this transaction; see [`duplicates::merge`].
+async fn merge_transactions(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MergeRequest>,
+) -> Result<Json<Transaction>, AppError> {
+    Ok(Json(
+        duplicates::merge(&pool, user_id, id, payload.duplicate_id).await?,
+    ))
 }
 
 async fn delete_transaction(
@@ -224,15 +853,20 @@ async fn delete_transaction(
     State(pool): State<DbPool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>, AppError> {
-    sqlx::query!(
+    let account_id = sqlx::query_scalar!(
         "DELETE FROM transactions t 
          USING accounts a 
-         WHERE t.account_id = a.id AND t.id = $1 AND a.user_id = $2",
+         WHERE t.account_id = a.id AND t.id = $1 AND a.user_id = $2
+         RETURNING t.account_id",
         id,
         user_id
     )
-    .execute(&pool)
+    .fetch_optional(&pool)
     .await?;
 
+    if let Some(account_id) = account_id {
+        balances::refresh(&pool, account_id).await?;
+    }
+
     Ok(Json(()))
 }
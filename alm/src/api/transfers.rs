@@ -0,0 +1,189 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    db::{models::Transfer, DbPool},
+    transfers,
+    utils::{auth::AuthUser, AppError},
+};
+
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route("/", get(list_transfers).post(link_transfer))
+        .route("/detect", post(detect_transfers))
+        .route("/:id", delete(unlink_transfer))
+        .with_state(pool)
+}
+
+#[derive(Serialize)]
+struct TransferLeg {
+    transaction_id: Uuid,
+    account_id: Uuid,
+    date: NaiveDate,
+    amount: Decimal,
+    description: String,
+}
+
+#[derive(Serialize)]
+struct TransferResponse {
+    id: Uuid,
+    source: String,
+    outflow: TransferLeg,
+    inflow: TransferLeg,
+}
+
+async fn list_transfers(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+) -> Result<Json<Vec<TransferResponse>>, AppError> {
+    let rows = sqlx::query!(
+        "SELECT tr.id, tr.source,
+            o.id as outflow_id, o.account_id as outflow_account_id, o.date as outflow_date,
+            o.amount as outflow_amount, o.description as outflow_description,
+            i.id as inflow_id, i.account_id as inflow_account_id, i.date as inflow_date,
+            i.amount as inflow_amount, i.description as inflow_description
+         FROM transfers tr
+         JOIN transactions o ON tr.outflow_transaction_id = o.id
+         JOIN transactions i ON tr.inflow_transaction_id = i.id
+         WHERE tr.user_id = $1 AND tr.status = 'linked'
+         ORDER BY o.date DESC, tr.id",
+        user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let transfers = rows
+        .into_iter()
+        .map(|r| TransferResponse {
+            id: r.id,
+            source: r.source,
+            outflow: TransferLeg {
+                transaction_id: r.outflow_id,
+                account_id: r.outflow_account_id,
+                date: r.outflow_date,
+                amount: r.outflow_amount,
+                description: r.outflow_description,
+            },
+            inflow: TransferLeg {
+                transaction_id: r.inflow_id,
+                account_id: r.inflow_account_id,
+                date: r.inflow_date,
+                amount: r.inflow_amount,
+                description: r.inflow_description,
+            },
+        })
+        .collect();
+
+    Ok(Json(transfers))
+}
+
+#[derive(Deserialize)]
+struct LinkTransferRequest {
+    outflow_transaction_id: Uuid,
+    inflow_transaction_id: Uuid,
+}
+
+/// Links two transactions by hand. Unlike detection, the amounts may differ,
+/// e.g. for a transfer between accounts in different currencies.
+async fn link_transfer(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(payload): Json<LinkTransferRequest>,
+) -> Result<Json<Transfer>, AppError> {
+    let legs = sqlx::query!(
+        "SELECT t.id, t.account_id, t.amount,
+            EXISTS (
+                SELECT 1 FROM transfers tr
+                WHERE tr.status = 'linked'
+                AND (tr.outflow_transaction_id = t.id OR tr.inflow_transaction_id = t.id)
+            ) as \"linked!\"
+         FROM transactions t
+         JOIN accounts a ON t.account_id = a.id
+         WHERE a.user_id = $1 AND t.id IN ($2, $3)",
+        user_id,
+        payload.outflow_transaction_id,
+        payload.inflow_transaction_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let leg = |id| legs.iter().find(|l| l.id == id).ok_or(AppError::NotFound);
+    let outflow = leg(payload.outflow_transaction_id)?;
+    let inflow = leg(payload.inflow_transaction_id)?;
+
+    if outflow.amount >= Decimal::ZERO || inflow.amount <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "A transfer links a negative outflow to a positive inflow".to_string(),
+        ));
+    }
+
+    if outflow.account_id == inflow.account_id {
+        return Err(AppError::BadRequest(
+            "Both sides of a transfer are in the same account".to_string(),
+        ));
+    }
+
+    if outflow.linked || inflow.linked {
+        return Err(AppError::BadRequest(
+            "Transaction is already part of a transfer".to_string(),
+        ));
+    }
+
+    let transfer = sqlx::query_as!(
+        Transfer,
+        "INSERT INTO transfers (user_id, outflow_transaction_id, inflow_transaction_id, source)
+         VALUES ($1, $2, $3, 'manual')
+         ON CONFLICT (outflow_transaction_id, inflow_transaction_id) DO UPDATE SET
+            status = 'linked',
+            source = 'manual',
+            updated_at = NOW()
+         RETURNING *",
+        user_id,
+        payload.outflow_transaction_id,
+        payload.inflow_transaction_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(transfer))
+}
+
+/// The pair is remembered as unlinked so detection won't link it again, and
+/// both transactions count as income and spending once more.
+async fn unlink_transfer(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<()>, AppError> {
+    sqlx::query!(
+        "UPDATE transfers SET status = 'unlinked', updated_at = NOW()
+         WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(()))
+}
+
+#[derive(Serialize)]
+struct DetectTransfersResponse {
+    linked: u64,
+}
+
+async fn detect_transfers(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+) -> Result<Json<DetectTransfersResponse>, AppError> {
+    let linked = transfers::detect(&pool, user_id).await?;
+
+    Ok(Json(DetectTransfersResponse { linked }))
+}
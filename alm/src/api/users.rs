@@ -0,0 +1,90 @@
+use axum::{extract::State, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    utils::{auth::AuthUser, AppError},
+};
+
+pub fn routes(pool: DbPool) -> Router {
+    Router::new()
+        .route("/me", get(get_profile).put(update_profile))
+        .with_state(pool)
+}
+
+#[derive(Serialize)]
+struct Profile {
+    id: Uuid,
+    email: String,
+    reporting_currency: String,
+    week_start_day: i16,
+    created_at: DateTime<Utc>,
+}
+
+async fn get_profile(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+) -> Result<Json<Profile>, AppError> {
+    let profile = sqlx::query_as!(
+        Profile,
+        "SELECT id, email, reporting_currency, week_start_day, created_at FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(profile))
+}
+
+#[derive(Deserialize)]
+struct UpdateProfileRequest {
+    reporting_currency: Option<String>,
+    /// ISO weekday weekly analytics start on, 1 (Monday) to 7 (Sunday).
+    week_start_day: Option<i16>,
+}
+
+async fn update_profile(
+    AuthUser { user_id }: AuthUser,
+    State(pool): State<DbPool>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<Profile>, AppError> {
+    let currency = payload.reporting_currency.map(|c| c.trim().to_uppercase());
+
+    if let Some(currency) = &currency {
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(AppError::BadRequest(
+                "reporting_currency must be a three-letter ISO 4217 code".to_string(),
+            ));
+        }
+    }
+
+    if payload
+        .week_start_day
+        .is_some_and(|day| !(1..=7).contains(&day))
+    {
+        return Err(AppError::BadRequest(
+            "week_start_day must be from 1 (Monday) to 7 (Sunday)".to_string(),
+        ));
+    }
+
+    let profile = sqlx::query_as!(
+        Profile,
+        "UPDATE users SET
+            reporting_currency = COALESCE($1, reporting_currency),
+            week_start_day = COALESCE($2, week_start_day),
+            updated_at = NOW()
+         WHERE id = $3
+         RETURNING id, email, reporting_currency, week_start_day, created_at",
+        currency,
+        payload.week_start_day,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(profile))
+}
@@ -0,0 +1,90 @@
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::Classification;
+use crate::{db::DbPool, utils::AppError};
+
+/// Where a day's balance came from. Backfilled balances are estimates and
+/// give way to recorded ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Sync,
+    Manual,
+}
+
+impl Source {
+    fn as_str(self) -> &'static str {
+        match self {
+            Source::Sync => "sync",
+            Source::Manual => "manual",
+        }
+    }
+}
+
+/// Records an account's balance on a day, replacing whatever was known.
+pub async fn record(
+    pool: &DbPool,
+    account_id: Uuid,
+    date: NaiveDate,
+    balance: Decimal,
+    source: Source,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO account_balance_history (account_id, date, balance, source)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (account_id, date) DO UPDATE SET
+            balance = EXCLUDED.balance,
+            source = EXCLUDED.source,
+            updated_at = NOW()",
+        account_id,
+        date,
+        balance,
+        source.as_str()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Fills in daily balances from the account's first settled transaction up
+/// to yesterday: each day's balance is the current one minus everything
+/// posted after that day. Liability balances are amounts owed, so spending
+/// raised them instead. Recorded days are kept as they are.
+pub async fn backfill(pool: &DbPool, account_id: Uuid) -> Result<u64, AppError> {
+    let account = sqlx::query!(
+        "SELECT balance, account_type, classification FROM accounts WHERE id = $1",
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let today = Utc::now().date_naive();
+
+    let result = sqlx::query!(
+        "INSERT INTO account_balance_history (account_id, date, balance, source)
+         SELECT $1, d::date, $2 - $3 * COALESCE((
+            SELECT SUM(t.amount) FROM transactions t
+            WHERE t.account_id = $1 AND NOT t.pending AND t.date > d::date
+         ), 0), 'backfill'
+         FROM generate_series(
+            (SELECT MIN(date) FROM transactions WHERE account_id = $1 AND NOT pending),
+            $4::date - 1,
+            '1 day'
+         ) d
+         ON CONFLICT (account_id, date) DO UPDATE SET
+            balance = EXCLUDED.balance,
+            updated_at = NOW()
+         WHERE account_balance_history.source = 'backfill'",
+        account_id,
+        account.balance,
+        Classification::of(&account.account_type, account.classification.as_deref()).direction(),
+        today
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
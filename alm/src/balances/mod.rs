@@ -0,0 +1,90 @@
+pub mod history;
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{db::DbPool, utils::AppError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Classification {
+    Asset,
+    Liability,
+}
+
+impl Classification {
+    /// The user's choice when there is one. Otherwise credit cards and loans,
+    /// whose balances are what is owed, are liabilities.
+    pub fn of(account_type: &str, classification: Option<&str>) -> Self {
+        match classification {
+            Some("liability") => Classification::Liability,
+            Some(_) => Classification::Asset,
+            None if matches!(account_type, "credit" | "loan") => Classification::Liability,
+            None => Classification::Asset,
+        }
+    }
+
+    /// Which way a transaction moves the balance: spending lowers an asset
+    /// but raises what is owed on a liability.
+    fn direction(self) -> Decimal {
+        match self {
+            Classification::Asset => Decimal::ONE,
+            Classification::Liability => Decimal::NEGATIVE_ONE,
+        }
+    }
+}
+
+/// Recomputes a manual account's balance from its latest balance entry and,
+/// when the account derives its balance from transactions, every transaction
+/// dated after that entry (or all of them, without an entry). Accounts with
+/// neither keep their balance; Plaid accounts are refreshed by sync instead.
+/// Today's balance goes into the account's history, and derived accounts
+/// have their earlier days backfilled.
+pub async fn refresh(pool: &DbPool, account_id: Uuid) -> Result<(), AppError> {
+    let Some(account) = sqlx::query!(
+        "SELECT account_type, classification, balance_from_transactions FROM accounts
+         WHERE id = $1 AND plaid_account_id IS NULL",
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let balance = sqlx::query_scalar!(
+        "WITH latest AS (
+            SELECT date, balance FROM account_balance_entries
+            WHERE account_id = $1
+            ORDER BY date DESC
+            LIMIT 1
+         )
+         UPDATE accounts a SET balance = CASE
+            WHEN a.balance_from_transactions THEN
+                COALESCE((SELECT balance FROM latest), 0)
+                + $2 * COALESCE((
+                    SELECT SUM(t.amount) FROM transactions t
+                    WHERE t.account_id = a.id
+                    AND t.date > COALESCE((SELECT date FROM latest), '-infinity'::date)
+                ), 0)
+            ELSE COALESCE((SELECT balance FROM latest), a.balance)
+         END
+         WHERE a.id = $1
+         RETURNING a.balance",
+        account_id,
+        Classification::of(&account.account_type, account.classification.as_deref()).direction()
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let today = Utc::now().date_naive();
+    history::record(pool, account_id, today, balance, history::Source::Manual).await?;
+
+    if account.balance_from_transactions {
+        history::backfill(pool, account_id).await?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -10,6 +11,8 @@ pub struct User {
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub reporting_currency: String,
+    pub week_start_day: i16,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -20,10 +23,28 @@ pub struct Account {
     pub plaid_item_id: Option<String>,
     pub account_name: String,
     pub account_type: String,
-    pub balance: f64,
+    pub balance: Decimal,
     pub currency: String,
     pub last_synced: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub account_subtype: Option<String>,
+    pub available_balance: Option<Decimal>,
+    pub credit_limit: Option<Decimal>,
+    pub mask: Option<String>,
+    pub official_name: Option<String>,
+    pub balance_from_transactions: bool,
+    pub classification: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct BalanceEntry {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub date: NaiveDate,
+    pub balance: Decimal,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -32,13 +53,57 @@ pub struct Transaction {
     pub account_id: Uuid,
     pub plaid_transaction_id: Option<String>,
     pub date: NaiveDate,
-    pub amount: f64,
+    pub amount: Decimal,
     pub description: String,
     pub category_id: Option<Uuid>,
     pub merchant_name: Option<String>,
     pub pending: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub notes: Option<String>,
+    pub plaid_category_primary: Option<String>,
+    pub plaid_category_detailed: Option<String>,
+    pub import_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TransactionSplit {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Transfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub outflow_transaction_id: Uuid,
+    pub inflow_transaction_id: Uuid,
+    pub status: String,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ImportProfile {
+    pub account_id: Uuid,
+    pub delimiter: String,
+    pub has_header: bool,
+    pub date_column: i32,
+    pub date_format: String,
+    pub description_column: i32,
+    pub amount_column: Option<i32>,
+    pub debit_column: Option<i32>,
+    pub credit_column: Option<i32>,
+    pub negate_amounts: bool,
+    pub decimal_comma: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -52,12 +117,31 @@ pub struct Category {
     pub is_default: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CategorizationRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub priority: i32,
+    pub match_type: String,
+    pub merchant_pattern: Option<String>,
+    pub description_pattern: Option<String>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub account_id: Option<Uuid>,
+    pub set_category_id: Option<Uuid>,
+    pub set_description: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Budget {
     pub id: Uuid,
     pub user_id: Uuid,
     pub category_id: Uuid,
-    pub amount: f64,
+    pub amount: Decimal,
     pub period: String,
     pub start_date: NaiveDate,
     pub end_date: Option<NaiveDate>,
@@ -74,4 +158,17 @@ pub struct PlaidItem {
     pub institution_name: String,
     pub status: String,
     pub created_at: DateTime<Utc>,
+    pub transactions_cursor: Option<String>,
+    pub encrypted_data_key: Option<String>,
+    pub encryption_key_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Institution {
+    pub institution_id: String,
+    pub name: String,
+    pub logo: Option<String>,
+    pub primary_color: Option<String>,
+    pub url: Option<String>,
+    pub updated_at: DateTime<Utc>,
 }
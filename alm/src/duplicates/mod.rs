@@ -0,0 +1,335 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{
+    balances,
+    db::{models::Transaction, DbPool},
+    utils::AppError,
+};
+
+/// How many days apart two copies of the same purchase may be dated; a
+/// pending charge can take a few days to post.
+const MATCH_WINDOW_DAYS: i32 = 5;
+
+/// Minimum description similarity for two transactions to be duplicates.
+const MIN_SIMILARITY: f64 = 0.5;
+
+#[derive(Serialize)]
+pub struct DuplicatePair {
+    /// The copy whose category, description and notes are worth keeping.
+    pub keep: Transaction,
+    pub duplicate: Transaction,
+    /// Description similarity between 0 and 1.
+    pub similarity: f64,
+}
+
+fn bigrams(text: &str) -> HashMap<(char, char), u32> {
+    let normalized: Vec<char> = text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+
+    let mut counts = HashMap::new();
+    for pair in normalized.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_default() += 1;
+    }
+    counts
+}
+
+/// Dice coefficient over character bigrams, ignoring case and punctuation:
+/// 1 for identical text, 0 for nothing in common.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (bigrams(a), bigrams(b));
+    let total: u32 = a.values().sum::<u32>() + b.values().sum::<u32>();
+
+    if total == 0 {
+        return 0.0;
+    }
+
+    let shared: u32 = a
+        .iter()
+        .map(|(pair, count)| (*count).min(b.get(pair).copied().unwrap_or(0)))
+        .sum();
+
+    f64::from(2 * shared) / f64::from(total)
+}
+
+/// The closest match between two transactions' descriptions and merchant
+/// names.
+fn best_similarity(a: &Transaction, b: &Transaction) -> f64 {
+    let names = |t: &Transaction| {
+        let mut names = vec![t.description.clone()];
+        names.extend(t.merchant_name.clone());
+        names
+    };
+
+    let (a, b) = (names(a), names(b));
+    a.iter()
+        .flat_map(|x| b.iter().map(move |y| similarity(x, y)))
+        .fold(0.0, f64::max)
+}
+
+/// Prefers the copy the user has categorized or annotated, then a manual
+/// entry, then the settled one.
+fn keep_first(a: Transaction, b: Transaction) -> (Transaction, Transaction) {
+    let rank = |t: &Transaction| {
+        (
+            t.category_id.is_some() || t.notes.is_some(),
+            t.plaid_transaction_id.is_none(),
+            !t.pending,
+        )
+    };
+
+    if rank(&b) > rank(&a) {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+/// Finds transactions recorded twice: the same amount in the same account a
+/// few days apart with similar descriptions, where at least one copy is a
+/// manual entry or still pending. Two settled Plaid transactions are taken to
+/// be separate purchases. Each transaction appears in at most one pair, the
+/// most similar first.
+pub async fn find(pool: &DbPool, user_id: Uuid) -> Result<Vec<DuplicatePair>, AppError> {
+    let candidates = sqlx::query!(
+        "SELECT x.id as first_id, y.id as second_id
+         FROM transactions x
+         JOIN transactions y ON y.account_id = x.account_id
+            AND y.amount = x.amount
+            AND y.id > x.id
+            AND y.date BETWEEN x.date - $2::int AND x.date + $2::int
+         JOIN accounts a ON x.account_id = a.id
+         WHERE a.user_id = $1
+         AND (
+            x.plaid_transaction_id IS NULL
+            OR y.plaid_transaction_id IS NULL
+            OR x.pending <> y.pending
+         )",
+        user_id,
+        MATCH_WINDOW_DAYS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let ids: Vec<Uuid> = candidates
+        .iter()
+        .flat_map(|c| [c.first_id, c.second_id])
+        .collect();
+
+    let mut transactions: HashMap<Uuid, Transaction> = sqlx::query_as!(
+        Transaction,
+        "SELECT * FROM transactions WHERE id = ANY($1)",
+        &ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|t| (t.id, t))
+    .collect();
+
+    let mut scored: Vec<(f64, Uuid, Uuid)> = candidates
+        .iter()
+        .filter_map(|c| {
+            let score = best_similarity(
+                transactions.get(&c.first_id)?,
+                transactions.get(&c.second_id)?,
+            );
+            (score >= MIN_SIMILARITY).then_some((score, c.first_id, c.second_id))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut used = HashSet::new();
+    let mut pairs = Vec::new();
+
+    for (score, first, second) in scored {
+        if used.contains(&first) || used.contains(&second) {
+            continue;
+        }
+        used.insert(first);
+        used.insert(second);
+
+        let (Some(a), Some(b)) = (transactions.remove(&first), transactions.remove(&second)) else {
+            continue;
+        };
+        let (keep, duplicate) = keep_first(a, b);
+
+        pairs.push(DuplicatePair {
+            keep,
+            duplicate,
+            similarity: (score * 100.0).round() / 100.0,
+        });
+    }
+
+    pairs.sort_by(|a, b| {
+        b.keep
+            .date
+            .cmp(&a.keep.date)
+            .then(a.keep.id.cmp(&b.keep.id))
+    });
+
+    Ok(pairs)
+}
+
+/// Folds `duplicate_id` into `id` and deletes it. The user's category,
+/// description and notes on `id` stay; when the duplicate came from Plaid (or
+/// is the settled copy of a pending charge) its Plaid id, date, amount and
+/// status are adopted, so later syncs update the merged transaction. Splits
+/// and transfers move over only if `id` has none of its own.
+pub async fn merge(
+    pool: &DbPool,
+    user_id: Uuid,
+    id: Uuid,
+    duplicate_id: Uuid,
+) -> Result<Transaction, AppError> {
+    if id == duplicate_id {
+        return Err(AppError::BadRequest(
+            "A transaction cannot be merged into itself".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let rows = sqlx::query_as!(
+        Transaction,
+        "SELECT t.* FROM transactions t
+         JOIN accounts a ON t.account_id = a.id
+         WHERE t.id IN ($1, $2) AND a.user_id = $3
+         FOR UPDATE OF t",
+        id,
+        duplicate_id,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let row = |id| rows.iter().find(|t| t.id == id).ok_or(AppError::NotFound);
+    let keep = row(id)?;
+    let duplicate = row(duplicate_id)?;
+
+    if keep.account_id != duplicate.account_id
+        || keep.amount.is_sign_negative() != duplicate.amount.is_sign_negative()
+    {
+        return Err(AppError::BadRequest(
+            "Only transactions in the same account and in the same direction can be merged"
+                .to_string(),
+        ));
+    }
+
+    let adopt_plaid = duplicate.plaid_transaction_id.is_some()
+        && (keep.plaid_transaction_id.is_none() || (keep.pending && !duplicate.pending));
+    let source = if adopt_plaid { duplicate } else { keep };
+
+    sqlx::query!(
+        "UPDATE transaction_splits SET transaction_id = $1, updated_at = NOW()
+         WHERE transaction_id = $2
+         AND NOT EXISTS (SELECT 1 FROM transaction_splits WHERE transaction_id = $1)",
+        id,
+        duplicate_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE transfers SET
+            outflow_transaction_id = CASE WHEN outflow_transaction_id = $2
+                THEN $1 ELSE outflow_transaction_id END,
+            inflow_transaction_id = CASE WHEN inflow_transaction_id = $2
+                THEN $1 ELSE inflow_transaction_id END,
+            updated_at = NOW()
+         WHERE (outflow_transaction_id = $2 OR inflow_transaction_id = $2)
+         AND NOT EXISTS (
+            SELECT 1 FROM transfers
+            WHERE outflow_transaction_id = $1 OR inflow_transaction_id = $1
+         )",
+        id,
+        duplicate_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE merged_import_ids SET transaction_id = $1
+         WHERE transaction_id = $2
+         AND import_id NOT IN (SELECT import_id FROM merged_import_ids WHERE transaction_id = $1)",
+        id,
+        duplicate_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Deleted first so its Plaid and import ids are free to move. Keeping the
+    // import id stops a re-import of the statement from bringing it back.
+    sqlx::query!("DELETE FROM transactions WHERE id = $1", duplicate_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let merged = sqlx::query_as!(
+        Transaction,
+        "UPDATE transactions SET
+            plaid_transaction_id = $2,
+            date = $3,
+            amount = $4,
+            pending = $5,
+            merchant_name = $6,
+            plaid_category_primary = $7,
+            plaid_category_detailed = $8,
+            category_id = $9,
+            notes = $10,
+            import_id = COALESCE(import_id, $11),
+            updated_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+        id,
+        source.plaid_transaction_id,
+        source.date,
+        source.amount,
+        source.pending,
+        source.merchant_name.clone().or(keep.merchant_name.clone()),
+        source.plaid_category_primary,
+        source.plaid_category_detailed,
+        keep.category_id.or(duplicate.category_id),
+        keep.notes.clone().or(duplicate.notes.clone()),
+        duplicate.import_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // The kept row can only carry one import id; remember the other.
+    if let Some(import_id) = &duplicate.import_id {
+        if merged.import_id.as_ref() != Some(import_id) {
+            sqlx::query!(
+                "INSERT INTO merged_import_ids (transaction_id, import_id) VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                id,
+                import_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    // Splits that no longer add up to the adopted amount are dropped, as on sync.
+    sqlx::query!(
+        "DELETE FROM transaction_splits WHERE transaction_id = $1
+         AND (SELECT SUM(amount) FROM transaction_splits WHERE transaction_id = $1) <> $2",
+        id,
+        merged.amount
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    balances::refresh(pool, merged.account_id).await?;
+
+    Ok(merged)
+}
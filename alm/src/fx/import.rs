@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use crate::{db::DbPool, utils::AppError};
+
+/// ECB reference rates are quoted as units of currency per euro.
+const ECB_BASE_CURRENCY: &str = "EUR";
+
+const BATCH_SIZE: usize = 5000;
+
+#[derive(Debug, PartialEq)]
+pub struct FxRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate_date: NaiveDate,
+    pub rate: Decimal,
+}
+
+/// Parses either the ECB reference rate CSV (`Date,USD,JPY,...` with one row
+/// per day, as in `eurofxref.csv` and `eurofxref-hist.csv`) or a long format
+/// with a `date,base,quote,rate` header.
+pub fn parse(csv: &str) -> Result<Vec<FxRate>, AppError> {
+    let mut lines = csv.lines().filter(|l| !l.trim().is_empty());
+
+    let header: Vec<String> = lines
+        .next()
+        .ok_or_else(|| AppError::BadRequest("Empty FX rate file".to_string()))?
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .collect();
+
+    let is_long = header.len() == 4
+        && header
+            .iter()
+            .zip(["date", "base", "quote", "rate"])
+            .all(|(h, expected)| h.eq_ignore_ascii_case(expected));
+
+    let mut rates = Vec::new();
+
+    for line in lines {
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        let rate_date = parse_date(cells[0])?;
+
+        if is_long {
+            let [_, base, quote, rate] = cells[..] else {
+                return Err(AppError::BadRequest(format!("Malformed FX row '{}'", line)));
+            };
+
+            rates.push(FxRate {
+                base_currency: base.to_uppercase(),
+                quote_currency: quote.to_uppercase(),
+                rate_date,
+                rate: parse_rate(rate)?,
+            });
+            continue;
+        }
+
+        for (currency, value) in header.iter().zip(&cells).skip(1) {
+            // The historical file has trailing empty columns and N/A for
+            // currencies that were not quoted that day.
+            if currency.is_empty() || value.is_empty() || *value == "N/A" {
+                continue;
+            }
+
+            rates.push(FxRate {
+                base_currency: ECB_BASE_CURRENCY.to_string(),
+                quote_currency: currency.to_uppercase(),
+                rate_date,
+                rate: parse_rate(value)?,
+            });
+        }
+    }
+
+    Ok(rates)
+}
+
+/// Upserts rates, replacing any existing fixing for the same pair and day.
+pub async fn store(pool: &DbPool, rates: &[FxRate]) -> Result<u64, AppError> {
+    let mut stored = 0;
+
+    for batch in rates.chunks(BATCH_SIZE) {
+        let bases: Vec<&str> = batch.iter().map(|r| r.base_currency.as_str()).collect();
+        let quotes: Vec<&str> = batch.iter().map(|r| r.quote_currency.as_str()).collect();
+        let dates: Vec<NaiveDate> = batch.iter().map(|r| r.rate_date).collect();
+        let values: Vec<Decimal> = batch.iter().map(|r| r.rate).collect();
+
+        stored += sqlx::query!(
+            "INSERT INTO fx_rates (base_currency, quote_currency, rate_date, rate)
+             SELECT * FROM UNNEST($1::text[], $2::text[], $3::date[], $4::numeric[])
+             ON CONFLICT (base_currency, quote_currency, rate_date)
+             DO UPDATE SET rate = EXCLUDED.rate",
+            &bases as &[&str],
+            &quotes as &[&str],
+            &dates,
+            &values
+        )
+        .execute(pool)
+        .await?
+        .rows_affected();
+    }
+
+    Ok(stored)
+}
+
+pub async fn load_file(pool: &DbPool, path: &str) -> Result<u64, AppError> {
+    let csv = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path, e)))?;
+
+    store(pool, &parse(&csv)?).await
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    // The daily ECB file writes dates as "15 January 2024".
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%d %B %Y"))
+        .map_err(|_| AppError::BadRequest(format!("Invalid FX rate date '{}'", value)))
+}
+
+fn parse_rate(value: &str) -> Result<Decimal, AppError> {
+    Decimal::from_str(value)
+        .ok()
+        .filter(|rate| *rate > Decimal::ZERO)
+        .ok_or_else(|| AppError::BadRequest(format!("Invalid FX rate '{}'", value)))
+}
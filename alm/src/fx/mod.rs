@@ -0,0 +1,182 @@
+pub mod import;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::{db::DbPool, utils::AppError};
+
+/// An amount in a single currency, used to report totals before conversion.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencyAmount {
+    pub currency: String,
+    pub amount: Decimal,
+}
+
+pub async fn reporting_currency(pool: &DbPool, user_id: Uuid) -> Result<String, AppError> {
+    sqlx::query_scalar!(
+        "SELECT reporting_currency FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// The distinct currencies a report touches, including the one it is
+/// reported in.
+pub fn currency_set<'a>(
+    currencies: impl Iterator<Item = &'a String>,
+    reporting_currency: &str,
+) -> Vec<String> {
+    let mut currencies: Vec<String> = currencies.cloned().collect();
+    currencies.push(reporting_currency.to_string());
+    currencies.sort();
+    currencies.dedup();
+    currencies
+}
+
+/// Daily rates, as units of the quote currency per unit of the base, for the
+/// currencies and dates a report covers.
+#[derive(Default)]
+pub struct FxRates {
+    rates: HashMap<(String, String), BTreeMap<NaiveDate, Decimal>>,
+}
+
+impl FxRates {
+    /// Loads every rate involving `currencies` between `from` and `to`, plus
+    /// the last rate before `from` so days without a fixing (weekends, bank
+    /// holidays) fall back to the previous one.
+    pub async fn load(
+        pool: &DbPool,
+        currencies: &[String],
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Self, AppError> {
+        let rows = sqlx::query!(
+            "SELECT base_currency, quote_currency, rate_date, rate
+             FROM fx_rates r
+             WHERE (base_currency = ANY($1) OR quote_currency = ANY($1))
+             AND rate_date <= $3
+             AND rate_date >= COALESCE(
+                (SELECT MAX(p.rate_date) FROM fx_rates p
+                 WHERE p.base_currency = r.base_currency
+                 AND p.quote_currency = r.quote_currency
+                 AND p.rate_date <= $2),
+                $2
+             )",
+            currencies,
+            from,
+            to
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut rates = Self::default();
+
+        for row in rows {
+            rates.insert(
+                &row.base_currency,
+                &row.quote_currency,
+                row.rate_date,
+                row.rate,
+            );
+        }
+
+        Ok(rates)
+    }
+
+    pub fn insert(&mut self, base: &str, quote: &str, date: NaiveDate, rate: Decimal) {
+        self.rates
+            .entry((base.to_string(), quote.to_string()))
+            .or_default()
+            .insert(date, rate);
+    }
+
+    /// Units of `to` per unit of `from` on `date`, using a direct, inverse or
+    /// cross rate through a shared base currency such as EUR.
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        if let Some(rate) = self.latest(from, to, date) {
+            return Some(rate);
+        }
+
+        if let Some(rate) = self.latest(to, from, date) {
+            return Decimal::ONE.checked_div(rate);
+        }
+
+        self.rates
+            .keys()
+            .filter(|(_, quote)| quote == from)
+            .find_map(|(base, _)| {
+                let base_to_from = self.latest(base, from, date)?;
+                let base_to_to = self.latest(base, to, date)?;
+                base_to_to.checked_div(base_to_from)
+            })
+    }
+
+    /// Converts `amount` at the rate for `date`, rounded to cents.
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+    ) -> Result<Decimal, AppError> {
+        let rate = self.rate(from, to, date).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "No exchange rate from {} to {} on or before {}",
+                from, to, date
+            ))
+        })?;
+
+        Ok((amount * rate).round_dp(2))
+    }
+
+    fn latest(&self, base: &str, quote: &str, date: NaiveDate) -> Option<Decimal> {
+        self.rates
+            .get(&(base.to_string(), quote.to_string()))?
+            .range(..=date)
+            .next_back()
+            .map(|(_, rate)| *rate)
+    }
+}
+
+/// A running total over mixed currencies: each amount is converted as it is
+/// added, and the unconverted sums are kept per currency.
+#[derive(Default)]
+pub struct MixedTotal {
+    pub converted: Decimal,
+    original: BTreeMap<String, Decimal>,
+}
+
+impl MixedTotal {
+    pub fn add(
+        &mut self,
+        rates: &FxRates,
+        amount: Decimal,
+        currency: &str,
+        reporting_currency: &str,
+        date: NaiveDate,
+    ) -> Result<(), AppError> {
+        self.converted += rates.convert(amount, currency, reporting_currency, date)?;
+        *self.original.entry(currency.to_string()).or_default() += amount;
+
+        Ok(())
+    }
+
+    pub fn original(&self) -> Vec<CurrencyAmount> {
+        self.original
+            .iter()
+            .map(|(currency, amount)| CurrencyAmount {
+                currency: currency.clone(),
+                amount: *amount,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::NaiveDate;
+
+use super::{parse_amount, ContentIds, Statement, StatementLine};
+use crate::db::models::ImportProfile;
+
+/// Splits CSV text into records, each with the line it starts on. Quoted
+/// fields may contain the delimiter, doubled quotes and line breaks.
+fn records(content: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+
+    records.retain(|(_, fields)| fields.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Reads a CSV export with the account's column mapping. Split debit and
+/// credit columns hold unsigned amounts; a single amount column is signed,
+/// flipped with `negate_amounts` for banks that print spending as positive.
+pub fn parse(content: &str, profile: &ImportProfile) -> Statement {
+    let delimiter = profile.delimiter.chars().next().unwrap_or(',');
+    let mut statement = Statement::default();
+    let mut ids = ContentIds::default();
+
+    let records = records(content, delimiter);
+    let skip = usize::from(profile.has_header);
+
+    for (line, fields) in records.into_iter().skip(skip) {
+        let column = |index: Option<i32>| {
+            index
+                .and_then(|i| fields.get(i as usize))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+
+        let Some(date) = column(Some(profile.date_column)) else {
+            statement.error(line, "Missing date");
+            continue;
+        };
+        let Ok(date) = NaiveDate::parse_from_str(date, &profile.date_format) else {
+            statement.error(
+                line,
+                format!("Date {:?} does not match {}", date, profile.date_format),
+            );
+            continue;
+        };
+
+        let amount = |index: Option<i32>| match column(index) {
+            Some(text) => parse_amount(text, profile.decimal_comma)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid amount {:?}", text)),
+            None => Ok(None),
+        };
+
+        let amount = match (
+            amount(profile.amount_column),
+            amount(profile.debit_column),
+            amount(profile.credit_column),
+        ) {
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                statement.error(line, e);
+                continue;
+            }
+            (Ok(Some(amount)), _, _) => {
+                if profile.negate_amounts {
+                    -amount
+                } else {
+                    amount
+                }
+            }
+            (Ok(None), Ok(debit), Ok(credit)) if debit.is_some() || credit.is_some() => {
+                credit.unwrap_or_default().abs() - debit.unwrap_or_default().abs()
+            }
+            _ => {
+                statement.error(line, "Missing amount");
+                continue;
+            }
+        };
+
+        let description = column(Some(profile.description_column))
+            .unwrap_or_default()
+            .to_string();
+        if description.is_empty() {
+            statement.error(line, "Missing description");
+            continue;
+        }
+
+        statement.lines.push(StatementLine {
+            import_id: ids.next(date, amount, &description),
+            date,
+            amount,
+            description,
+        });
+    }
+
+    statement
+}
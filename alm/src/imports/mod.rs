@@ -0,0 +1,298 @@
+pub mod csv;
+pub mod ofx;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+use uuid::Uuid;
+
+use crate::{
+    balances,
+    db::{models::ImportProfile, DbPool},
+    rules::{RuleInput, RuleSet},
+    transfers,
+    utils::AppError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    Csv,
+    Ofx,
+    Qfx,
+}
+
+impl StatementFormat {
+    /// OFX and QFX files carry an `<OFX>` element; anything else is read as
+    /// CSV.
+    pub fn detect(content: &str) -> Self {
+        if content
+            .as_bytes()
+            .windows(5)
+            .any(|w| w.eq_ignore_ascii_case(b"<OFX>"))
+        {
+            StatementFormat::Ofx
+        } else {
+            StatementFormat::Csv
+        }
+    }
+}
+
+/// One transaction read from a statement file. Amounts follow the app's
+/// convention: money out is negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementLine {
+    pub import_id: String,
+    pub date: NaiveDate,
+    pub amount: Decimal,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Statement {
+    pub lines: Vec<StatementLine>,
+    pub errors: Vec<LineError>,
+}
+
+impl Statement {
+    fn error(&mut self, line: usize, message: impl Into<String>) {
+        self.errors.push(LineError {
+            line,
+            message: message.into(),
+        });
+    }
+}
+
+/// Ids for lines without a FITID, hashed from their content. Identical lines
+/// in one file (two coffees on the same day) are told apart by how many came
+/// before them, so the same file always yields the same ids.
+#[derive(Default)]
+struct ContentIds(HashMap<String, usize>);
+
+impl ContentIds {
+    fn next(&mut self, date: NaiveDate, amount: Decimal, description: &str) -> String {
+        let key = format!(
+            "{}|{}|{}",
+            date,
+            amount.normalize(),
+            description.trim().to_lowercase()
+        );
+        let seen = self.0.entry(key.clone()).or_default();
+        *seen += 1;
+
+        format!("hash:{:x}", Sha256::digest(format!("{}|{}", key, seen)))
+    }
+}
+
+/// Reads an amount as banks print it: currency symbols, thousands separators
+/// and spaces are ignored, and `(12.50)` or `12.50-` are negative.
+fn parse_amount(text: &str, decimal_comma: bool) -> Option<Decimal> {
+    let text = text.trim();
+    let (text, parenthesized) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => (inner.trim(), true),
+        None => (text, false),
+    };
+    let (text, trailing_minus) = match text.strip_suffix('-') {
+        Some(rest) => (rest, true),
+        None => (text, false),
+    };
+
+    let decimal_separator = if decimal_comma { ',' } else { '.' };
+    let number: String = text
+        .chars()
+        .filter_map(|c| match c {
+            '0'..='9' | '-' | '+' => Some(c),
+            c if c == decimal_separator => Some('.'),
+            _ => None,
+        })
+        .collect();
+
+    if !number.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let amount = Decimal::from_str(&number).ok()?;
+    Some(if parenthesized || trailing_minus {
+        -amount.abs()
+    } else {
+        amount
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedLine {
+    pub import_id: String,
+    pub date: NaiveDate,
+    pub amount: Decimal,
+    pub description: String,
+    pub category_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSummary {
+    pub format: StatementFormat,
+    pub committed: bool,
+    /// Transactions that would be added, or were added when committed, after
+    /// categorization rules.
+    pub transactions: Vec<ImportedLine>,
+    /// Lines skipped because they were imported before.
+    pub duplicates: usize,
+    /// Lines that could not be read; the rest of the file is still imported.
+    pub errors: Vec<LineError>,
+}
+
+/// Reads a statement file into a manual account. Without `commit` nothing is
+/// written and the summary is a preview of what committing would add. Lines
+/// already imported, by FITID or content hash, are skipped either way, even
+/// when they have since been merged into another transaction.
+pub async fn import(
+    pool: &DbPool,
+    user_id: Uuid,
+    account_id: Uuid,
+    content: &str,
+    format: Option<StatementFormat>,
+    commit: bool,
+) -> Result<ImportSummary, AppError> {
+    let plaid_account_id = sqlx::query_scalar!(
+        "SELECT plaid_account_id FROM accounts WHERE id = $1 AND user_id = $2",
+        account_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if plaid_account_id.is_some() {
+        return Err(AppError::BadRequest(
+            "Transactions for accounts linked to Plaid are imported by sync".to_string(),
+        ));
+    }
+
+    let format = format.unwrap_or_else(|| StatementFormat::detect(content));
+
+    let statement = match format {
+        StatementFormat::Csv => {
+            let profile = sqlx::query_as!(
+                ImportProfile,
+                "SELECT * FROM import_profiles WHERE account_id = $1",
+                account_id
+            )
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| {
+                AppError::BadRequest("Save a CSV import profile for this account first".to_string())
+            })?;
+
+            csv::parse(content, &profile)
+        }
+        StatementFormat::Ofx | StatementFormat::Qfx => ofx::parse(content),
+    };
+
+    let ids: Vec<String> = statement
+        .lines
+        .iter()
+        .map(|l| l.import_id.clone())
+        .collect();
+
+    let existing: HashSet<String> = sqlx::query_scalar!(
+        "SELECT import_id as \"import_id!\" FROM transactions
+         WHERE account_id = $1 AND import_id = ANY($2)
+         UNION
+         SELECT m.import_id FROM merged_import_ids m
+         JOIN transactions t ON m.transaction_id = t.id
+         WHERE t.account_id = $1 AND m.import_id = ANY($2)",
+        account_id,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let rules = RuleSet::load(pool, user_id).await?;
+    let mut seen = HashSet::new();
+    let mut duplicates = 0;
+    let mut lines = Vec::new();
+
+    for line in statement.lines {
+        if existing.contains(&line.import_id) || !seen.insert(line.import_id.clone()) {
+            duplicates += 1;
+            continue;
+        }
+
+        let outcome = rules.apply(&RuleInput {
+            account_id,
+            description: &line.description,
+            merchant_name: None,
+            amount: line.amount,
+        });
+
+        lines.push(ImportedLine {
+            import_id: line.import_id,
+            date: line.date,
+            amount: line.amount,
+            description: outcome.description.unwrap_or(line.description),
+            category_id: outcome.category_id,
+        });
+    }
+
+    if commit && !lines.is_empty() {
+        let dates: Vec<NaiveDate> = lines.iter().map(|l| l.date).collect();
+        let amounts: Vec<Decimal> = lines.iter().map(|l| l.amount).collect();
+        let descriptions: Vec<String> = lines.iter().map(|l| l.description.clone()).collect();
+        let category_ids: Vec<Option<Uuid>> = lines.iter().map(|l| l.category_id).collect();
+        let import_ids: Vec<String> = lines.iter().map(|l| l.import_id.clone()).collect();
+
+        // A concurrent import of the same file may have won some lines.
+        let inserted: HashSet<String> = sqlx::query_scalar!(
+            "INSERT INTO transactions (account_id, date, amount, description, category_id, import_id, pending)
+             SELECT $1, l.date, l.amount, l.description, l.category_id, l.import_id, false
+             FROM UNNEST($2::date[], $3::numeric[], $4::text[], $5::uuid[], $6::text[])
+                AS l(date, amount, description, category_id, import_id)
+             ON CONFLICT (account_id, import_id) WHERE import_id IS NOT NULL DO NOTHING
+             RETURNING import_id as \"import_id!\"",
+            account_id,
+            &dates,
+            &amounts,
+            &descriptions,
+            &category_ids as &[Option<Uuid>],
+            &import_ids
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        duplicates += lines.len() - inserted.len();
+        lines.retain(|l| inserted.contains(&l.import_id));
+
+        balances::refresh(pool, account_id).await?;
+
+        if let Err(e) = transfers::detect(pool, user_id).await {
+            tracing::warn!(
+                "Transfer detection failed for account {}: {:?}",
+                account_id,
+                e
+            );
+        }
+    }
+
+    Ok(ImportSummary {
+        format,
+        committed: commit,
+        transactions: lines,
+        duplicates,
+        errors: statement.errors,
+    })
+}
@@ -0,0 +1,81 @@
+use chrono::NaiveDate;
+
+use super::{parse_amount, ContentIds, Statement, StatementLine};
+
+/// The text of `<TAG>` within an element. OFX 1.x (SGML) leaves leaf elements
+/// unclosed, so the value runs to the next tag either way.
+fn value<'a>(element: &'a str, upper: &str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = upper.find(&open)? + open.len();
+    let end = element[start..]
+        .find('<')
+        .map_or(element.len(), |i| start + i);
+
+    Some(element[start..end].trim()).filter(|v| !v.is_empty())
+}
+
+/// `YYYYMMDD`, optionally followed by a time and timezone that are ignored.
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.get(..8)?, "%Y%m%d").ok()
+}
+
+/// Reads the `<STMTTRN>` entries of an OFX or QFX file (both the SGML and the
+/// XML flavour). FITIDs identify transactions across overlapping downloads;
+/// entries without one fall back to a content hash.
+pub fn parse(content: &str) -> Statement {
+    let upper = content.to_ascii_uppercase();
+    let mut statement = Statement::default();
+    let mut ids = ContentIds::default();
+    let mut offset = 0;
+    let mut line = 1;
+
+    while let Some(found) = upper[offset..].find("<STMTTRN>") {
+        let start = offset + found;
+        line += content[offset..start].matches('\n').count();
+        let end = upper[start..]
+            .find("</STMTTRN>")
+            .map_or(content.len(), |i| start + i);
+        let element = &content[start..end];
+        let element_upper = &upper[start..end];
+        let field = |tag| value(element, element_upper, tag);
+
+        let at = line;
+        line += element.matches('\n').count();
+        offset = end;
+
+        let Some(date) = field("DTPOSTED").and_then(parse_date) else {
+            statement.error(at, "Missing or invalid DTPOSTED");
+            continue;
+        };
+
+        let Some(amount) =
+            field("TRNAMT").and_then(|a| parse_amount(a, a.contains(',') && !a.contains('.')))
+        else {
+            statement.error(at, "Missing or invalid TRNAMT");
+            continue;
+        };
+
+        let Some(description) = field("NAME")
+            .or_else(|| field("PAYEE"))
+            .or_else(|| field("MEMO"))
+        else {
+            statement.error(at, "Missing NAME");
+            continue;
+        };
+        let description = description.to_string();
+
+        let import_id = match field("FITID") {
+            Some(fitid) => format!("fitid:{}", fitid),
+            None => ids.next(date, amount, &description),
+        };
+
+        statement.lines.push(StatementLine {
+            import_id,
+            date,
+            amount,
+            description,
+        });
+    }
+
+    statement
+}
@@ -1,4 +1,10 @@
 mod api;
+pub mod balances;
 mod db;
-mod plaid;
-mod utils;
+pub mod duplicates;
+pub mod fx;
+pub mod imports;
+pub mod plaid;
+pub mod rules;
+pub mod transfers;
+pub mod utils;
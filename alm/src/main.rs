@@ -3,7 +3,7 @@ use axum::{
     Router,
 };
 use sqlx::postgres::PgPoolOptions;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber;
 
@@ -29,15 +29,52 @@ async fn main() -> anyhow::Result<()> {
 
     sqlx::migrate!("./migrations").run(&pool).await?;
 
+    // Parsed once, failing fast on a missing or malformed key ring rather
+    // than on first use.
+    let keys = Arc::new(utils::crypto::KeyRing::from_env());
+
+    if std::env::args().nth(1).as_deref() == Some("rotate-token-keys") {
+        let rotated = plaid::tokens::rotate_access_tokens(&pool, &keys)
+            .await
+            .map_err(|e| anyhow::anyhow!("Key rotation failed: {:?}", e))?;
+        tracing::info!(
+            "Moved {} Plaid access tokens to key '{}'",
+            rotated,
+            keys.active_key_id()
+        );
+        return Ok(());
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("load-fx-rates") {
+        let path = std::env::args()
+            .nth(2)
+            .ok_or_else(|| anyhow::anyhow!("Usage: load-fx-rates <file.csv>"))?;
+        let loaded = fx::import::load_file(&pool, &path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Loading FX rates failed: {:?}", e))?;
+        tracing::info!("Loaded {} FX rates from {}", loaded, path);
+        return Ok(());
+    }
+
+    let plaid = plaid::PlaidClient::new();
+
+    plaid::scheduler::spawn(pool.clone(), plaid.clone(), keys.clone());
+
     let app = Router::new()
         .route("/health", get(health_check))
         .nest("/api/auth", api::auth::routes(pool.clone()))
-        .nest("/api/accounts", api::accounts::routes(pool.clone()))
+        .nest("/api/users", api::users::routes(pool.clone()))
+        .nest(
+            "/api/accounts",
+            api::accounts::routes(pool.clone(), plaid.clone(), keys.clone()),
+        )
         .nest("/api/transactions", api::transactions::routes(pool.clone()))
+        .nest("/api/transfers", api::transfers::routes(pool.clone()))
         .nest("/api/categories", api::categories::routes(pool.clone()))
+        .nest("/api/rules", api::rules::routes(pool.clone()))
         .nest("/api/budgets", api::budgets::routes(pool.clone()))
         .nest("/api/analytics", api::analytics::routes(pool.clone()))
-        .nest("/api/plaid", api::plaid::routes(pool.clone()))
+        .nest("/api/plaid", api::plaid::routes(pool.clone(), plaid, keys))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{db::DbPool, plaid::client::PersonalFinanceCategory, utils::AppError};
+
+/// The `plaid_category_mappings` table, from Plaid personal finance
+/// categories to app categories.
+pub struct CategoryMap {
+    mappings: HashMap<String, Uuid>,
+}
+
+impl CategoryMap {
+    pub async fn load(pool: &DbPool) -> Result<Self, AppError> {
+        let mappings =
+            sqlx::query!("SELECT plaid_category, category_id FROM plaid_category_mappings")
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|m| (m.plaid_category, m.category_id))
+                .collect();
+
+        Ok(Self { mappings })
+    }
+
+    /// The mapping for the detailed category if there is one, otherwise for
+    /// its primary category.
+    pub fn category_for(&self, category: &PersonalFinanceCategory) -> Option<Uuid> {
+        self.mappings
+            .get(&category.detailed)
+            .or_else(|| self.mappings.get(&category.primary))
+            .copied()
+    }
+}
@@ -1,12 +1,15 @@
 use chrono::{Duration, Utc};
-use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::error::{PlaidError, PlaidErrorBody};
 
 #[derive(Clone)]
 pub struct PlaidClient {
     client: reqwest::Client,
     client_id: String,
     secret: String,
-    env: String,
+    base_url: String,
 }
 
 impl PlaidClient {
@@ -15,33 +18,87 @@ impl PlaidClient {
         let secret = std::env::var("PLAID_SECRET").expect("PLAID_SECRET must be set");
         let env = std::env::var("PLAID_ENV").unwrap_or_else(|_| "sandbox".to_string());
 
+        let base_url = match env.as_str() {
+            "production" => "https://production.plaid.com",
+            "development" => "https://development.plaid.com",
+            _ => "https://sandbox.plaid.com",
+        };
+
+        Self::with_base_url(client_id, secret, base_url.to_string())
+    }
+
+    /// Builds a client against an explicit Plaid host, e.g. a local mock server in tests.
+    pub fn with_base_url(client_id: String, secret: String, base_url: String) -> Self {
         Self {
             client: reqwest::Client::new(),
             client_id,
             secret,
-            env,
+            base_url,
         }
     }
 
     fn base_url(&self) -> &str {
-        match self.env.as_str() {
-            "production" => "https://production.plaid.com",
-            "development" => "https://development.plaid.com",
-            _ => "https://sandbox.plaid.com",
+        &self.base_url
+    }
+
+    /// Posts to a Plaid endpoint, decoding Plaid's error envelope on failure.
+    async fn post<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        path: &str,
+        request: &Req,
+    ) -> Result<Resp, PlaidError> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url(), path))
+            .json(request)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response.json::<Resp>().await?);
         }
+
+        let body = response.text().await?;
+
+        match serde_json::from_str::<PlaidErrorBody>(&body) {
+            Ok(error) => Err(error.into()),
+            Err(_) => Err(PlaidError::UnexpectedResponse { status, body }),
+        }
+    }
+
+    pub async fn create_link_token(&self, user_id: String) -> Result<String, PlaidError> {
+        self.link_token(user_id, None).await
+    }
+
+    /// Creates a Link token in update mode for an existing item, letting the
+    /// user repair its login without relinking. The access token is unchanged
+    /// once the flow completes, so there is no public token to exchange.
+    pub async fn create_update_link_token(
+        &self,
+        user_id: String,
+        access_token: &str,
+    ) -> Result<String, PlaidError> {
+        self.link_token(user_id, Some(access_token.to_string()))
+            .await
     }
 
-    pub async fn create_link_token(
+    async fn link_token(
         &self,
         user_id: String,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+        access_token: Option<String>,
+    ) -> Result<String, PlaidError> {
         #[derive(Serialize)]
         struct LinkTokenRequest {
             client_id: String,
             secret: String,
             user: User,
             client_name: String,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
             products: Vec<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            access_token: Option<String>,
             country_codes: Vec<String>,
             language: String,
         }
@@ -56,6 +113,12 @@ impl PlaidClient {
             link_token: String,
         }
 
+        // Update mode must not request products; the item keeps its existing ones.
+        let products = match access_token {
+            Some(_) => Vec::new(),
+            None => vec!["transactions".to_string()],
+        };
+
         let request = LinkTokenRequest {
             client_id: self.client_id.clone(),
             secret: self.secret.clone(),
@@ -63,19 +126,13 @@ impl PlaidClient {
                 client_user_id: user_id,
             },
             client_name: "Finance Budget App".to_string(),
-            products: vec!["transactions".to_string()],
+            products,
+            access_token,
             country_codes: vec!["US".to_string()],
             language: "en".to_string(),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/link/token/create", self.base_url()))
-            .json(&request)
-            .send()
-            .await?
-            .json::<LinkTokenResponse>()
-            .await?;
+        let response: LinkTokenResponse = self.post("/link/token/create", &request).await?;
 
         Ok(response.link_token)
     }
@@ -83,7 +140,7 @@ impl PlaidClient {
     pub async fn exchange_public_token(
         &self,
         public_token: &str,
-    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+    ) -> Result<(String, String), PlaidError> {
         #[derive(Serialize)]
         struct ExchangeRequest {
             client_id: String,
@@ -103,22 +160,12 @@ impl PlaidClient {
             public_token: public_token.to_string(),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/item/public_token/exchange", self.base_url()))
-            .json(&request)
-            .send()
-            .await?
-            .json::<ExchangeResponse>()
-            .await?;
+        let response: ExchangeResponse = self.post("/item/public_token/exchange", &request).await?;
 
         Ok((response.access_token, response.item_id))
     }
 
-    pub async fn get_item(
-        &self,
-        access_token: &str,
-    ) -> Result<ItemInfo, Box<dyn std::error::Error>> {
+    pub async fn get_item(&self, access_token: &str) -> Result<ItemInfo, PlaidError> {
         #[derive(Serialize)]
         struct ItemRequest {
             client_id: String,
@@ -142,27 +189,78 @@ impl PlaidClient {
             access_token: access_token.to_string(),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/item/get", self.base_url()))
-            .json(&request)
-            .send()
-            .await?
-            .json::<ItemResponse>()
-            .await?;
-
-        let institution_id = response.item.institution_id.unwrap_or_default();
+        let response: ItemResponse = self.post("/item/get", &request).await?;
 
         Ok(ItemInfo {
-            institution_id: institution_id.clone(),
-            institution_name: institution_id,
+            institution_id: response.item.institution_id.unwrap_or_default(),
         })
     }
 
-    pub async fn get_accounts(
+    /// Looks up an institution's display metadata. `logo` is a base64-encoded
+    /// PNG and `primary_color` a hex code; either may be missing.
+    pub async fn get_institution(
         &self,
-        access_token: &str,
-    ) -> Result<Vec<AccountInfo>, Box<dyn std::error::Error>> {
+        institution_id: &str,
+    ) -> Result<InstitutionInfo, PlaidError> {
+        #[derive(Serialize)]
+        struct InstitutionRequest {
+            client_id: String,
+            secret: String,
+            institution_id: String,
+            country_codes: Vec<String>,
+            options: InstitutionOptions,
+        }
+
+        #[derive(Serialize)]
+        struct InstitutionOptions {
+            include_optional_metadata: bool,
+        }
+
+        #[derive(Deserialize)]
+        struct InstitutionResponse {
+            institution: InstitutionInfo,
+        }
+
+        let request = InstitutionRequest {
+            client_id: self.client_id.clone(),
+            secret: self.secret.clone(),
+            institution_id: institution_id.to_string(),
+            country_codes: vec!["US".to_string()],
+            options: InstitutionOptions {
+                include_optional_metadata: true,
+            },
+        };
+
+        let response: InstitutionResponse = self.post("/institutions/get_by_id", &request).await?;
+
+        Ok(response.institution)
+    }
+
+    /// Revokes the access token and stops billing for the item. Plaid cannot
+    /// undo this; the user would have to link the institution again.
+    pub async fn remove_item(&self, access_token: &str) -> Result<(), PlaidError> {
+        #[derive(Serialize)]
+        struct RemoveItemRequest {
+            client_id: String,
+            secret: String,
+            access_token: String,
+        }
+
+        #[derive(Deserialize)]
+        struct RemoveItemResponse {}
+
+        let request = RemoveItemRequest {
+            client_id: self.client_id.clone(),
+            secret: self.secret.clone(),
+            access_token: access_token.to_string(),
+        };
+
+        let _: RemoveItemResponse = self.post("/item/remove", &request).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_accounts(&self, access_token: &str) -> Result<Vec<AccountInfo>, PlaidError> {
         #[derive(Serialize)]
         struct AccountsRequest {
             client_id: String,
@@ -179,14 +277,21 @@ impl PlaidClient {
         struct Account {
             account_id: String,
             name: String,
+            official_name: Option<String>,
             #[serde(rename = "type")]
             account_type: String,
+            subtype: Option<String>,
+            mask: Option<String>,
             balances: Balances,
         }
 
         #[derive(Deserialize)]
         struct Balances {
-            current: Option<f64>,
+            current: Option<Decimal>,
+            available: Option<Decimal>,
+            limit: Option<Decimal>,
+            iso_currency_code: Option<String>,
+            unofficial_currency_code: Option<String>,
         }
 
         let request = AccountsRequest {
@@ -195,14 +300,7 @@ impl PlaidClient {
             access_token: access_token.to_string(),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/accounts/get", self.base_url()))
-            .json(&request)
-            .send()
-            .await?
-            .json::<AccountsResponse>()
-            .await?;
+        let response: AccountsResponse = self.post("/accounts/get", &request).await?;
 
         Ok(response
             .accounts
@@ -210,8 +308,19 @@ impl PlaidClient {
             .map(|a| AccountInfo {
                 account_id: a.account_id,
                 name: a.name,
+                official_name: a.official_name,
                 account_type: a.account_type,
-                balance: a.balances.current.unwrap_or(0.0),
+                subtype: a.subtype,
+                mask: a.mask,
+                balance: a.balances.current.unwrap_or_default(),
+                available_balance: a.balances.available,
+                credit_limit: a.balances.limit,
+                // Crypto and other non-ISO balances only carry an unofficial code.
+                currency: a
+                    .balances
+                    .iso_currency_code
+                    .or(a.balances.unofficial_currency_code)
+                    .unwrap_or_else(|| "USD".to_string()),
             })
             .collect())
     }
@@ -220,7 +329,7 @@ impl PlaidClient {
         &self,
         access_token: &str,
         days: i64,
-    ) -> Result<Vec<TransactionInfo>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<TransactionInfo>, PlaidError> {
         #[derive(Serialize)]
         struct TransactionsRequest {
             client_id: String,
@@ -235,17 +344,6 @@ impl PlaidClient {
             transactions: Vec<Transaction>,
         }
 
-        #[derive(Deserialize)]
-        struct Transaction {
-            transaction_id: String,
-            account_id: String,
-            amount: f64,
-            date: String,
-            name: String,
-            merchant_name: Option<String>,
-            pending: bool,
-        }
-
         let end_date = Utc::now().date_naive();
         let start_date = end_date - Duration::days(days);
 
@@ -257,49 +355,193 @@ impl PlaidClient {
             end_date: end_date.to_string(),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/transactions/get", self.base_url()))
-            .json(&request)
-            .send()
-            .await?
-            .json::<TransactionsResponse>()
-            .await?;
+        let response: TransactionsResponse = self.post("/transactions/get", &request).await?;
 
         Ok(response
             .transactions
             .into_iter()
-            .map(|t| TransactionInfo {
-                transaction_id: t.transaction_id,
-                account_id: t.account_id,
-                amount: t.amount,
-                date: chrono::NaiveDate::parse_from_str(&t.date, "%Y-%m-%d").unwrap(),
-                name: t.name,
-                merchant_name: t.merchant_name,
-                pending: t.pending,
-            })
+            .map(TransactionInfo::from)
             .collect())
     }
+
+    /// Fetches one page of `/transactions/sync`. Pass the `next_cursor` of the
+    /// previous page (or the cursor stored on the item) to resume; `None` starts
+    /// from the beginning of the item's history.
+    pub async fn transactions_sync(
+        &self,
+        access_token: &str,
+        cursor: Option<String>,
+    ) -> Result<TransactionsSyncPage, PlaidError> {
+        #[derive(Serialize)]
+        struct SyncRequest {
+            client_id: String,
+            secret: String,
+            access_token: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cursor: Option<String>,
+            count: u32,
+        }
+
+        #[derive(Deserialize)]
+        struct SyncResponse {
+            added: Vec<Transaction>,
+            modified: Vec<Transaction>,
+            removed: Vec<RemovedTransaction>,
+            next_cursor: String,
+            has_more: bool,
+        }
+
+        #[derive(Deserialize)]
+        struct RemovedTransaction {
+            transaction_id: String,
+        }
+
+        let request = SyncRequest {
+            client_id: self.client_id.clone(),
+            secret: self.secret.clone(),
+            access_token: access_token.to_string(),
+            cursor,
+            count: 500,
+        };
+
+        let response: SyncResponse = self.post("/transactions/sync", &request).await?;
+
+        Ok(TransactionsSyncPage {
+            added: response
+                .added
+                .into_iter()
+                .map(TransactionInfo::from)
+                .collect(),
+            modified: response
+                .modified
+                .into_iter()
+                .map(TransactionInfo::from)
+                .collect(),
+            removed: response
+                .removed
+                .into_iter()
+                .map(|r| r.transaction_id)
+                .collect(),
+            next_cursor: response.next_cursor,
+            has_more: response.has_more,
+        })
+    }
+
+    /// Fetches the public JWK Plaid used to sign a webhook, identified by the
+    /// `kid` in the `Plaid-Verification` header.
+    pub async fn get_webhook_verification_key(
+        &self,
+        key_id: &str,
+    ) -> Result<WebhookVerificationKey, PlaidError> {
+        #[derive(Serialize)]
+        struct KeyRequest {
+            client_id: String,
+            secret: String,
+            key_id: String,
+        }
+
+        #[derive(Deserialize)]
+        struct KeyResponse {
+            key: WebhookVerificationKey,
+        }
+
+        let request = KeyRequest {
+            client_id: self.client_id.clone(),
+            secret: self.secret.clone(),
+            key_id: key_id.to_string(),
+        };
+
+        let response: KeyResponse = self.post("/webhook_verification_key/get", &request).await?;
+
+        Ok(response.key)
+    }
+}
+
+#[derive(Deserialize)]
+struct Transaction {
+    transaction_id: String,
+    account_id: String,
+    amount: Decimal,
+    date: chrono::NaiveDate,
+    name: String,
+    merchant_name: Option<String>,
+    pending: bool,
+    personal_finance_category: Option<PersonalFinanceCategory>,
+}
+
+impl From<Transaction> for TransactionInfo {
+    fn from(t: Transaction) -> Self {
+        TransactionInfo {
+            transaction_id: t.transaction_id,
+            account_id: t.account_id,
+            amount: t.amount,
+            date: t.date,
+            name: t.name,
+            merchant_name: t.merchant_name,
+            pending: t.pending,
+            personal_finance_category: t.personal_finance_category,
+        }
+    }
 }
 
 pub struct ItemInfo {
     pub institution_id: String,
-    pub institution_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct InstitutionInfo {
+    pub institution_id: String,
+    pub name: String,
+    pub logo: Option<String>,
+    pub primary_color: Option<String>,
+    pub url: Option<String>,
 }
 
 pub struct AccountInfo {
     pub account_id: String,
     pub name: String,
+    pub official_name: Option<String>,
     pub account_type: String,
-    pub balance: f64,
+    pub subtype: Option<String>,
+    pub mask: Option<String>,
+    pub balance: Decimal,
+    pub available_balance: Option<Decimal>,
+    pub credit_limit: Option<Decimal>,
+    pub currency: String,
 }
 
 pub struct TransactionInfo {
     pub transaction_id: String,
     pub account_id: String,
-    pub amount: f64,
+    pub amount: Decimal,
     pub date: chrono::NaiveDate,
     pub name: String,
     pub merchant_name: Option<String>,
     pub pending: bool,
+    pub personal_finance_category: Option<PersonalFinanceCategory>,
+}
+
+/// Plaid's own categorization, e.g. `FOOD_AND_DRINK` /
+/// `FOOD_AND_DRINK_GROCERIES`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PersonalFinanceCategory {
+    pub primary: String,
+    pub detailed: String,
+}
+
+pub struct TransactionsSyncPage {
+    pub added: Vec<TransactionInfo>,
+    pub modified: Vec<TransactionInfo>,
+    pub removed: Vec<String>,
+    pub next_cursor: String,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct WebhookVerificationKey {
+    pub kid: String,
+    pub alg: String,
+    pub x: String,
+    pub y: String,
+    pub expired_at: Option<i64>,
 }
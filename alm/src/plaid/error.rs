@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+/// Plaid's error envelope, returned with any non-2xx response.
+#[derive(Debug, Deserialize)]
+pub struct PlaidErrorBody {
+    pub error_type: String,
+    pub error_code: String,
+    pub error_message: String,
+    pub display_message: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlaidError {
+    #[error("Plaid request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Unexpected Plaid response ({status}): {body}")]
+    UnexpectedResponse {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    /// The user has to go back through Link (update mode) before this item
+    /// can be used again.
+    #[error("Bank login required: {}", .0.error_message)]
+    ItemLoginRequired(Box<PlaidErrorBody>),
+
+    #[error("Plaid rate limit exceeded: {}", .0.error_message)]
+    RateLimited(Box<PlaidErrorBody>),
+
+    #[error("Institution unavailable: {}", .0.error_message)]
+    InstitutionUnavailable(Box<PlaidErrorBody>),
+
+    #[error("Plaid error {}/{}: {}", .0.error_type, .0.error_code, .0.error_message)]
+    Api(Box<PlaidErrorBody>),
+}
+
+impl From<PlaidErrorBody> for PlaidError {
+    fn from(body: PlaidErrorBody) -> Self {
+        match (body.error_type.as_str(), body.error_code.as_str()) {
+            (_, "ITEM_LOGIN_REQUIRED") => PlaidError::ItemLoginRequired(Box::new(body)),
+            ("RATE_LIMIT_EXCEEDED", _) => PlaidError::RateLimited(Box::new(body)),
+            ("INSTITUTION_ERROR", _) => PlaidError::InstitutionUnavailable(Box::new(body)),
+            _ => PlaidError::Api(Box::new(body)),
+        }
+    }
+}
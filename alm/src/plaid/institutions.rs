@@ -0,0 +1,56 @@
+use chrono::{Duration, Utc};
+
+use crate::{
+    db::{models::Institution, DbPool},
+    plaid::PlaidClient,
+    utils::AppError,
+};
+
+/// Names and logos rarely change, so cached rows are reused for a month.
+const CACHE_TTL_DAYS: i64 = 30;
+
+/// Returns an institution's metadata, fetching it from Plaid only when the
+/// cached row is missing or stale.
+pub async fn resolve(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    institution_id: &str,
+) -> Result<Institution, AppError> {
+    let cached = sqlx::query_as!(
+        Institution,
+        "SELECT * FROM institutions WHERE institution_id = $1",
+        institution_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(institution) = cached {
+        if Utc::now() - institution.updated_at < Duration::days(CACHE_TTL_DAYS) {
+            return Ok(institution);
+        }
+    }
+
+    let info = plaid.get_institution(institution_id).await?;
+
+    let institution = sqlx::query_as!(
+        Institution,
+        "INSERT INTO institutions (institution_id, name, logo, primary_color, url, updated_at)
+         VALUES ($1, $2, $3, $4, $5, NOW())
+         ON CONFLICT (institution_id) DO UPDATE SET
+            name = EXCLUDED.name,
+            logo = EXCLUDED.logo,
+            primary_color = EXCLUDED.primary_color,
+            url = EXCLUDED.url,
+            updated_at = NOW()
+         RETURNING *",
+        info.institution_id,
+        info.name,
+        info.logo,
+        info.primary_color,
+        info.url
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(institution)
+}
@@ -0,0 +1,132 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    db::{models::Account, DbPool},
+    plaid::{institutions, tokens, PlaidClient},
+    utils::{crypto::KeyRing, AppError},
+};
+
+#[derive(Serialize)]
+pub struct LinkedItem {
+    pub id: Uuid,
+    pub plaid_item_id: String,
+    pub institution_id: String,
+    pub institution_name: String,
+    pub accounts: Vec<Account>,
+}
+
+/// Exchanges a Link public token and stores the new item with its accounts.
+/// Once the exchange succeeds the item exists (and is billed) at Plaid, so any
+/// later failure removes it there again before the error is returned.
+pub async fn exchange(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    user_id: Uuid,
+    public_token: &str,
+) -> Result<LinkedItem, AppError> {
+    let (access_token, plaid_item_id) = plaid.exchange_public_token(public_token).await?;
+
+    let result = register(pool, plaid, keys, user_id, &access_token, plaid_item_id).await;
+
+    if result.is_err() {
+        if let Err(e) = plaid.remove_item(&access_token).await {
+            tracing::error!("Failed to remove Plaid item after a failed link: {:?}", e);
+        }
+    }
+
+    result
+}
+
+async fn register(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    user_id: Uuid,
+    access_token: &str,
+    plaid_item_id: String,
+) -> Result<LinkedItem, AppError> {
+    let item_info = plaid.get_item(access_token).await?;
+
+    // A failed lookup must not abort the link. Storing the id as the name lets
+    // the next sync fill it in.
+    let institution_id = item_info.institution_id;
+    let institution_name = if institution_id.is_empty() {
+        institution_id.clone()
+    } else {
+        match institutions::resolve(pool, plaid, &institution_id).await {
+            Ok(institution) => institution.name,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to resolve institution {} for item {}: {:?}",
+                    institution_id,
+                    plaid_item_id,
+                    e
+                );
+                institution_id.clone()
+            }
+        }
+    };
+
+    let account_infos = plaid.get_accounts(access_token).await?;
+
+    let sealed = tokens::seal_access_token(keys, access_token)?;
+
+    let mut tx = pool.begin().await?;
+
+    let item_id = Uuid::new_v4();
+
+    sqlx::query!(
+        "INSERT INTO plaid_items (id, user_id, plaid_access_token, encrypted_data_key, encryption_key_id, plaid_item_id, institution_id, institution_name)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        item_id,
+        user_id,
+        sealed.ciphertext,
+        sealed.data_key,
+        sealed.key_id,
+        plaid_item_id,
+        institution_id,
+        institution_name
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let mut accounts = Vec::with_capacity(account_infos.len());
+
+    for info in account_infos {
+        let account = sqlx::query_as!(
+            Account,
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, official_name, account_type, account_subtype, mask, balance, available_balance, credit_limit, currency, last_synced)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, NOW())
+             RETURNING *",
+            Uuid::new_v4(),
+            user_id,
+            info.account_id,
+            plaid_item_id,
+            info.name,
+            info.official_name,
+            info.account_type,
+            info.subtype,
+            info.mask,
+            info.balance,
+            info.available_balance,
+            info.credit_limit,
+            info.currency
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        accounts.push(account);
+    }
+
+    tx.commit().await?;
+
+    Ok(LinkedItem {
+        id: item_id,
+        plaid_item_id,
+        institution_id,
+        institution_name,
+        accounts,
+    })
+}
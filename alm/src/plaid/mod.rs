@@ -1,3 +1,12 @@
+pub mod categories;
 pub mod client;
+pub mod error;
+pub mod institutions;
+pub mod link;
+pub mod scheduler;
+pub mod sync;
+pub mod tokens;
+pub mod webhook;
 
 pub use client::PlaidClient;
+pub use error::PlaidError;
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    plaid::{sync, PlaidClient},
+    utils::crypto::KeyRing,
+};
+
+const DEFAULT_INTERVAL_SECS: u64 = 4 * 60 * 60;
+const MAX_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct Backoff {
+    failures: u32,
+    retry_at: Instant,
+}
+
+/// Starts the background worker that periodically syncs every active Plaid
+/// item. Items that fail are retried with exponential backoff so a single
+/// broken connection does not hammer Plaid on every tick.
+pub fn spawn(pool: DbPool, plaid: PlaidClient, keys: Arc<KeyRing>) -> JoinHandle<()> {
+    let interval = std::env::var("PLAID_SYNC_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_INTERVAL_SECS));
+
+    tokio::spawn(async move {
+        let mut backoff = HashMap::new();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            sync_all(&pool, &plaid, &keys, interval, &mut backoff).await;
+        }
+    })
+}
+
+async fn sync_all(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    interval: Duration,
+    backoff: &mut HashMap<Uuid, Backoff>,
+) {
+    // Items pending expiration still have working credentials until Plaid
+    // revokes them, so keep syncing them alongside active ones.
+    let item_ids = match sqlx::query_scalar!(
+        "SELECT id FROM plaid_items WHERE status IN ('active', 'pending_expiration')"
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::error!("Failed to load Plaid items for sync: {}", e);
+            return;
+        }
+    };
+
+    backoff.retain(|id, _| item_ids.contains(id));
+
+    for item_id in item_ids {
+        let now = Instant::now();
+
+        if backoff.get(&item_id).is_some_and(|b| b.retry_at > now) {
+            continue;
+        }
+
+        match sync::sync_item(pool, plaid, keys, item_id).await {
+            Ok(summary) => {
+                backoff.remove(&item_id);
+                tracing::info!(
+                    "Synced Plaid item {}: {} added, {} modified, {} removed",
+                    item_id,
+                    summary.added,
+                    summary.modified,
+                    summary.removed
+                );
+            }
+            Err(e) => {
+                let failures = backoff.get(&item_id).map_or(1, |b| b.failures + 1);
+                let delay = interval
+                    .saturating_mul(2u32.saturating_pow(failures))
+                    .min(MAX_BACKOFF);
+
+                tracing::warn!(
+                    "Plaid item {} failed to sync ({} consecutive failures), retrying in {:?}: {:?}",
+                    item_id,
+                    failures,
+                    delay,
+                    e
+                );
+
+                backoff.insert(
+                    item_id,
+                    Backoff {
+                        failures,
+                        retry_at: now + delay,
+                    },
+                );
+            }
+        }
+    }
+}
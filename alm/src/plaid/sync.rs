@@ -0,0 +1,332 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    balances::history::{self, Source},
+    db::{models::PlaidItem, DbPool},
+    plaid::{categories::CategoryMap, institutions, tokens, PlaidClient, PlaidError},
+    rules::{RuleInput, RuleSet},
+    transfers,
+    utils::{crypto::KeyRing, AppError},
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub modified: usize,
+    pub removed: usize,
+}
+
+/// Refreshes account balances and details and pulls new transactions for an
+/// item.
+pub async fn sync_item(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    item_id: Uuid,
+) -> Result<SyncSummary, AppError> {
+    let item = load_item(pool, item_id).await?;
+    let access_token = tokens::open_access_token(keys, &item)?;
+
+    if let Err(e) = backfill_institution_name(pool, plaid, &item).await {
+        tracing::warn!(
+            "Failed to resolve institution for item {}: {:?}",
+            item.id,
+            e
+        );
+    }
+
+    let result = match refresh_balances(pool, plaid, &item, &access_token).await {
+        Ok(()) => apply_transactions(pool, plaid, &item, &access_token).await,
+        Err(e) => Err(e),
+    };
+
+    // Walking back needs balances and transactions from the same sync.
+    if result.is_ok() {
+        if let Err(e) = backfill_history(pool, &item).await {
+            tracing::warn!(
+                "Balance history backfill failed for item {}: {:?}",
+                item.id,
+                e
+            );
+        }
+    }
+
+    flag_login_required(pool, item.id, result).await
+}
+
+/// Drains `/transactions/sync` for an item and applies the result in one
+/// database transaction. The stored cursor only advances once every page has
+/// been written, so a failed sync is retried from the same point.
+pub async fn sync_transactions(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    item_id: Uuid,
+) -> Result<SyncSummary, AppError> {
+    let item = load_item(pool, item_id).await?;
+    let access_token = tokens::open_access_token(keys, &item)?;
+
+    let result = apply_transactions(pool, plaid, &item, &access_token).await;
+
+    flag_login_required(pool, item.id, result).await
+}
+
+/// Moves an item to `login_required` when Plaid rejects its credentials, so
+/// background syncs stop until the user repairs it through Link update mode.
+pub(crate) async fn flag_login_required<T>(
+    pool: &DbPool,
+    item_id: Uuid,
+    result: Result<T, AppError>,
+) -> Result<T, AppError> {
+    if let Err(AppError::Plaid(PlaidError::ItemLoginRequired(_))) = &result {
+        sqlx::query!(
+            "UPDATE plaid_items SET status = 'login_required' WHERE id = $1",
+            item_id
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    result
+}
+
+/// Items linked before institution lookups, or whose lookup failed while
+/// linking, store the institution id as their name; replace it with the real
+/// one.
+async fn backfill_institution_name(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    item: &PlaidItem,
+) -> Result<(), AppError> {
+    if item.institution_id.is_empty() || item.institution_name != item.institution_id {
+        return Ok(());
+    }
+
+    let institution = institutions::resolve(pool, plaid, &item.institution_id).await?;
+
+    sqlx::query!(
+        "UPDATE plaid_items SET institution_name = $1 WHERE id = $2",
+        institution.name,
+        item.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_item(pool: &DbPool, item_id: Uuid) -> Result<PlaidItem, AppError> {
+    sqlx::query_as!(
+        PlaidItem,
+        "SELECT * FROM plaid_items WHERE id = $1",
+        item_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+async fn refresh_balances(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    item: &PlaidItem,
+    access_token: &str,
+) -> Result<(), AppError> {
+    let accounts = plaid.get_accounts(access_token).await?;
+    let today = Utc::now().date_naive();
+
+    for account in accounts {
+        let account_id = sqlx::query_scalar!(
+            "UPDATE accounts SET
+                balance = $1,
+                available_balance = $2,
+                credit_limit = $3,
+                currency = $4,
+                account_subtype = $5,
+                mask = $6,
+                official_name = $7,
+                last_synced = NOW()
+             WHERE user_id = $8 AND plaid_item_id = $9 AND plaid_account_id = $10
+             RETURNING id",
+            account.balance,
+            account.available_balance,
+            account.credit_limit,
+            account.currency,
+            account.subtype,
+            account.mask,
+            account.official_name,
+            item.user_id,
+            item.plaid_item_id,
+            account.account_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(account_id) = account_id {
+            history::record(pool, account_id, today, account.balance, Source::Sync).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn backfill_history(pool: &DbPool, item: &PlaidItem) -> Result<(), AppError> {
+    let account_ids = sqlx::query_scalar!(
+        "SELECT id FROM accounts WHERE user_id = $1 AND plaid_item_id = $2",
+        item.user_id,
+        item.plaid_item_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for account_id in account_ids {
+        history::backfill(pool, account_id).await?;
+    }
+
+    Ok(())
+}
+
+async fn apply_transactions(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    item: &PlaidItem,
+    access_token: &str,
+) -> Result<SyncSummary, AppError> {
+    let mut summary = SyncSummary::default();
+    let mut upserts = Vec::new();
+    let mut removed = Vec::new();
+    let mut modified_ids = Vec::new();
+    let mut cursor = item.transactions_cursor.clone();
+
+    loop {
+        let page = plaid
+            .transactions_sync(access_token, cursor.clone())
+            .await?;
+
+        summary.added += page.added.len();
+        summary.modified += page.modified.len();
+        summary.removed += page.removed.len();
+
+        modified_ids.extend(page.modified.iter().map(|t| t.transaction_id.clone()));
+        upserts.extend(page.added);
+        upserts.extend(page.modified);
+        removed.extend(page.removed);
+        cursor = Some(page.next_cursor);
+
+        if !page.has_more {
+            break;
+        }
+    }
+
+    let accounts: HashMap<String, Uuid> = sqlx::query!(
+        "SELECT id, plaid_account_id FROM accounts WHERE user_id = $1 AND plaid_item_id = $2",
+        item.user_id,
+        item.plaid_item_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter_map(|a| a.plaid_account_id.map(|plaid_id| (plaid_id, a.id)))
+    .collect();
+
+    let rules = RuleSet::load(pool, item.user_id).await?;
+    let plaid_categories = CategoryMap::load(pool).await?;
+
+    let mut tx = pool.begin().await?;
+
+    for t in upserts {
+        let Some(account_id) = accounts.get(&t.account_id) else {
+            continue;
+        };
+
+        // Plaid reports outflows as positive amounts; we store spending as negative.
+        let amount = -t.amount;
+
+        // Rules only shape new rows; updates leave category and description
+        // alone so user edits survive later syncs.
+        let outcome = rules.apply(&RuleInput {
+            account_id: *account_id,
+            description: &t.name,
+            merchant_name: t.merchant_name.as_deref(),
+            amount,
+        });
+
+        // A user's rule beats Plaid's own categorization.
+        let pfc = t.personal_finance_category.as_ref();
+        let category_id = outcome
+            .category_id
+            .or_else(|| pfc.and_then(|c| plaid_categories.category_for(c)));
+
+        sqlx::query!(
+            "INSERT INTO transactions (id, account_id, plaid_transaction_id, date, amount, description, merchant_name, pending, category_id, plaid_category_primary, plaid_category_detailed)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             ON CONFLICT (plaid_transaction_id) DO UPDATE SET
+                date = EXCLUDED.date,
+                amount = EXCLUDED.amount,
+                merchant_name = EXCLUDED.merchant_name,
+                pending = EXCLUDED.pending,
+                plaid_category_primary = EXCLUDED.plaid_category_primary,
+                plaid_category_detailed = EXCLUDED.plaid_category_detailed,
+                updated_at = NOW()",
+            Uuid::new_v4(),
+            account_id,
+            t.transaction_id,
+            t.date,
+            amount,
+            outcome.description.unwrap_or(t.name),
+            t.merchant_name,
+            t.pending,
+            category_id,
+            pfc.map(|c| c.primary.as_str()),
+            pfc.map(|c| c.detailed.as_str())
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Splits no longer add up once Plaid changes an amount (a tip posting,
+    // say), so drop them and let the user split the transaction again.
+    sqlx::query!(
+        "DELETE FROM transaction_splits WHERE transaction_id IN (
+            SELECT t.id FROM transactions t
+            JOIN transaction_splits s ON s.transaction_id = t.id
+            WHERE t.plaid_transaction_id = ANY($1)
+            GROUP BY t.id, t.amount
+            HAVING SUM(s.amount) <> t.amount
+         )",
+        &modified_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if !removed.is_empty() {
+        sqlx::query!(
+            "DELETE FROM transactions t
+             USING accounts a
+             WHERE t.account_id = a.id AND a.user_id = $1 AND t.plaid_transaction_id = ANY($2)",
+            item.user_id,
+            &removed
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        "UPDATE plaid_items SET transactions_cursor = $1 WHERE id = $2",
+        cursor,
+        item.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    if let Err(e) = transfers::detect(pool, item.user_id).await {
+        tracing::warn!("Transfer detection failed for item {}: {:?}", item.id, e);
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,61 @@
+use crate::{
+    db::{models::PlaidItem, DbPool},
+    utils::{
+        crypto::{KeyRing, Sealed},
+        AppError,
+    },
+};
+
+pub fn seal_access_token(keys: &KeyRing, access_token: &str) -> Result<Sealed, AppError> {
+    keys.seal(access_token)
+}
+
+pub fn open_access_token(keys: &KeyRing, item: &PlaidItem) -> Result<String, AppError> {
+    match (&item.encrypted_data_key, &item.encryption_key_id) {
+        (Some(data_key), Some(key_id)) => keys.open(&item.plaid_access_token, data_key, key_id),
+        // Written before encryption was introduced; sealed on the next rotation.
+        _ => Ok(item.plaid_access_token.clone()),
+    }
+}
+
+/// Moves every stored access token under the active master key. Sealed rows
+/// only have their data key re-wrapped; legacy plaintext rows are sealed.
+/// Returns the number of rows updated.
+pub async fn rotate_access_tokens(pool: &DbPool, keys: &KeyRing) -> Result<u64, AppError> {
+    let items = sqlx::query_as!(
+        PlaidItem,
+        "SELECT * FROM plaid_items WHERE encryption_key_id IS DISTINCT FROM $1",
+        keys.active_key_id()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+
+    for item in &items {
+        let sealed = match (&item.encrypted_data_key, &item.encryption_key_id) {
+            (Some(data_key), Some(key_id)) => Sealed {
+                ciphertext: item.plaid_access_token.clone(),
+                data_key: keys.rewrap(data_key, key_id)?,
+                key_id: keys.active_key_id().to_string(),
+            },
+            _ => keys.seal(&item.plaid_access_token)?,
+        };
+
+        sqlx::query!(
+            "UPDATE plaid_items
+             SET plaid_access_token = $1, encrypted_data_key = $2, encryption_key_id = $3
+             WHERE id = $4",
+            sealed.ciphertext,
+            sealed.data_key,
+            sealed.key_id,
+            item.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(items.len() as u64)
+}
@@ -0,0 +1,190 @@
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    db::DbPool,
+    plaid::{client::WebhookVerificationKey, sync, PlaidClient},
+    utils::{crypto::KeyRing, AppError},
+};
+
+/// Plaid signs webhooks at send time; anything older is treated as a replay.
+const MAX_WEBHOOK_AGE_SECS: i64 = 5 * 60;
+
+/// How far ahead of our clock a signature may be dated. Anything later could
+/// be replayed until it stops being in the future.
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Deserialize)]
+struct WebhookClaims {
+    iat: i64,
+    request_body_sha256: String,
+}
+
+/// Verifies `Plaid-Verification` JWTs, caching the signing keys by `kid`.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    plaid: PlaidClient,
+    keys: Arc<Mutex<HashMap<String, WebhookVerificationKey>>>,
+}
+
+impl WebhookVerifier {
+    pub fn new(plaid: PlaidClient) -> Self {
+        Self {
+            plaid,
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn verify(&self, token: &str, body: &[u8]) -> Result<(), AppError> {
+        let header = decode_header(token).map_err(|_| AppError::Unauthorized)?;
+
+        if header.alg != Algorithm::ES256 {
+            return Err(AppError::Unauthorized);
+        }
+
+        let kid = header.kid.ok_or(AppError::Unauthorized)?;
+        let key = self.key(&kid).await?;
+
+        let decoding_key =
+            DecodingKey::from_ec_components(&key.x, &key.y).map_err(|_| AppError::Unauthorized)?;
+
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+
+        let claims = decode::<WebhookClaims>(token, &decoding_key, &validation)
+            .map_err(|_| AppError::Unauthorized)?
+            .claims;
+
+        let age = chrono::Utc::now().timestamp() - claims.iat;
+
+        if !(-MAX_CLOCK_SKEW_SECS..=MAX_WEBHOOK_AGE_SECS).contains(&age) {
+            return Err(AppError::Unauthorized);
+        }
+
+        let body_hash = format!("{:x}", Sha256::digest(body));
+
+        if !constant_time_eq(body_hash.as_bytes(), claims.request_body_sha256.as_bytes()) {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(())
+    }
+
+    async fn key(&self, kid: &str) -> Result<WebhookVerificationKey, AppError> {
+        let cached = self.keys.lock().unwrap().get(kid).cloned();
+
+        if let Some(key) = cached {
+            return Ok(key);
+        }
+
+        let key = self.plaid.get_webhook_verification_key(kid).await?;
+
+        if key.expired_at.is_some() {
+            return Err(AppError::Unauthorized);
+        }
+
+        self.keys
+            .lock()
+            .unwrap()
+            .insert(kid.to_string(), key.clone());
+
+        Ok(key)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookPayload {
+    pub webhook_type: String,
+    pub webhook_code: String,
+    /// Missing on webhooks that aren't about a single item.
+    pub item_id: Option<String>,
+    pub error: Option<WebhookError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookError {
+    pub error_code: String,
+}
+
+/// Applies a verified webhook: transaction updates trigger a sync of a healthy
+/// item, item events update `plaid_items.status`. Webhooks without an item, or
+/// for an item that has since been removed, are ignored.
+pub async fn handle_webhook(
+    pool: &DbPool,
+    plaid: &PlaidClient,
+    keys: &KeyRing,
+    payload: WebhookPayload,
+) -> Result<(), AppError> {
+    let Some(plaid_item_id) = payload.item_id.as_deref() else {
+        tracing::debug!(
+            "Ignoring Plaid webhook {}/{} without an item",
+            payload.webhook_type,
+            payload.webhook_code
+        );
+        return Ok(());
+    };
+
+    let Some(item) = sqlx::query!(
+        "SELECT id, status FROM plaid_items WHERE plaid_item_id = $1",
+        plaid_item_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        tracing::debug!("Ignoring Plaid webhook for unknown item {}", plaid_item_id);
+        return Ok(());
+    };
+    let item_id = item.id;
+
+    let status = match (payload.webhook_type.as_str(), payload.webhook_code.as_str()) {
+        ("TRANSACTIONS", "SYNC_UPDATES_AVAILABLE" | "DEFAULT_UPDATE") => {
+            // Like the scheduler, leave broken items alone until Link update
+            // mode repairs them.
+            if matches!(item.status.as_str(), "active" | "pending_expiration") {
+                sync::sync_item(pool, plaid, keys, item_id).await?;
+            } else {
+                tracing::debug!(
+                    "Skipping sync for Plaid item {} with status {}",
+                    plaid_item_id,
+                    item.status
+                );
+            }
+            return Ok(());
+        }
+        ("ITEM", "ERROR") => match payload.error.as_ref().map(|e| e.error_code.as_str()) {
+            Some("ITEM_LOGIN_REQUIRED") => "login_required",
+            _ => "error",
+        },
+        ("ITEM", "PENDING_EXPIRATION") => "pending_expiration",
+        ("ITEM", "USER_PERMISSION_REVOKED") => "revoked",
+        ("ITEM", "LOGIN_REPAIRED") => "active",
+        _ => {
+            tracing::debug!(
+                "Ignoring Plaid webhook {}/{}",
+                payload.webhook_type,
+                payload.webhook_code
+            );
+            return Ok(());
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE plaid_items SET status = $1 WHERE id = $2",
+        status,
+        item_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
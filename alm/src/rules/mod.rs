@@ -0,0 +1,177 @@
+pub mod suggestions;
+
+use regex::{Regex, RegexBuilder};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    db::{models::CategorizationRule, DbPool},
+    utils::AppError,
+};
+
+/// Compiled patterns are capped so a single rule can't use unbounded memory.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// The parts of a transaction that rules match on.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInput<'a> {
+    pub account_id: Uuid,
+    pub description: &'a str,
+    pub merchant_name: Option<&'a str>,
+    pub amount: Decimal,
+}
+
+/// What the matching rules would change. Fields are `None` when no rule sets
+/// them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RuleOutcome {
+    pub category_id: Option<Uuid>,
+    pub description: Option<String>,
+}
+
+/// Case-insensitive text matcher for a rule's merchant or description.
+#[derive(Debug)]
+enum Pattern {
+    Contains(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(match_type: &str, pattern: &str) -> Result<Self, AppError> {
+        match match_type {
+            "contains" => Ok(Pattern::Contains(pattern.to_lowercase())),
+            "regex" => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .build()
+                .map(Pattern::Regex)
+                .map_err(|e| AppError::BadRequest(format!("Invalid pattern '{}': {}", pattern, e))),
+            other => Err(AppError::BadRequest(format!(
+                "Invalid match_type '{}', expected contains or regex",
+                other
+            ))),
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Pattern::Contains(needle) => text.to_lowercase().contains(needle),
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompiledRule {
+    rule: CategorizationRule,
+    merchant: Option<Pattern>,
+    description: Option<Pattern>,
+}
+
+impl CompiledRule {
+    pub fn compile(rule: CategorizationRule) -> Result<Self, AppError> {
+        let merchant = rule
+            .merchant_pattern
+            .as_deref()
+            .map(|p| Pattern::new(&rule.match_type, p))
+            .transpose()?;
+        let description = rule
+            .description_pattern
+            .as_deref()
+            .map(|p| Pattern::new(&rule.match_type, p))
+            .transpose()?;
+
+        Ok(Self {
+            rule,
+            merchant,
+            description,
+        })
+    }
+
+    pub fn rule(&self) -> &CategorizationRule {
+        &self.rule
+    }
+
+    /// Every condition the rule sets must hold. Amount bounds apply to the
+    /// absolute value, so the same rule covers purchases and refunds.
+    pub fn matches(&self, input: &RuleInput) -> bool {
+        if self
+            .rule
+            .account_id
+            .is_some_and(|id| id != input.account_id)
+        {
+            return false;
+        }
+
+        let amount = input.amount.abs();
+        if self.rule.min_amount.is_some_and(|min| amount < min)
+            || self.rule.max_amount.is_some_and(|max| amount > max)
+        {
+            return false;
+        }
+
+        if let Some(pattern) = &self.merchant {
+            if !input.merchant_name.is_some_and(|m| pattern.matches(m)) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.description {
+            if !pattern.matches(input.description) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A user's active rules in priority order.
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    pub async fn load(pool: &DbPool, user_id: Uuid) -> Result<Self, AppError> {
+        let rules = sqlx::query_as!(
+            CategorizationRule,
+            "SELECT * FROM categorization_rules
+             WHERE user_id = $1 AND is_active = true
+             ORDER BY priority DESC, created_at",
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| {
+                let id = rule.id;
+                // Patterns are validated on save; skip rather than fail imports
+                // if one somehow no longer compiles.
+                CompiledRule::compile(rule)
+                    .map_err(|e| tracing::warn!("Skipping rule {}: {:?}", id, e))
+                    .ok()
+            })
+            .collect();
+
+        Ok(Self { rules })
+    }
+
+    /// The highest-priority matching rule decides each field independently,
+    /// so a renaming rule and a categorizing rule can both apply.
+    pub fn apply(&self, input: &RuleInput) -> RuleOutcome {
+        let mut outcome = RuleOutcome::default();
+
+        for rule in self.rules.iter().filter(|r| r.matches(input)) {
+            if outcome.category_id.is_none() {
+                outcome.category_id = rule.rule.set_category_id;
+            }
+            if outcome.description.is_none() {
+                outcome.description = rule.rule.set_description.clone();
+            }
+        }
+
+        outcome
+    }
+}
@@ -0,0 +1,230 @@
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{db::DbPool, utils::AppError};
+
+/// How much of the user's categorized history the model learns from.
+const HISTORY_LIMIT: i64 = 10_000;
+
+/// Relative weight of each kind of evidence. A merchant match says more than
+/// shared description words, which say more than a similar amount at the same
+/// payee.
+const MERCHANT_WEIGHT: f64 = 3.0;
+const TOKEN_WEIGHT: f64 = 2.0;
+const AMOUNT_WEIGHT: f64 = 1.0;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Words that appear across unrelated merchants on bank statements.
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "pos", "debit", "credit", "card", "purchase", "payment", "online", "www",
+    "com", "inc", "llc", "ltd",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub category_id: Uuid,
+    /// Between 0 and 1.
+    pub confidence: f64,
+}
+
+/// Sign and order of magnitude, e.g. spending between $10 and $100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AmountBucket {
+    income: bool,
+    magnitude: u8,
+}
+
+impl AmountBucket {
+    fn of(amount: Decimal) -> Self {
+        let magnitude = amount
+            .abs()
+            .to_f64()
+            .filter(|a| *a >= 1.0)
+            .map(|a| a.log10().floor().min(5.0) as u8)
+            .unwrap_or(0);
+
+        Self {
+            income: amount > Decimal::ZERO,
+            magnitude,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    by_category: HashMap<Uuid, u32>,
+    total: u32,
+}
+
+impl Counts {
+    fn add(&mut self, category_id: Uuid) {
+        *self.by_category.entry(category_id).or_default() += 1;
+        self.total += 1;
+    }
+}
+
+/// Lowercase words from a bank description, without store numbers, card
+/// suffixes and other noise that differs between otherwise identical
+/// transactions.
+pub fn tokens(description: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    description
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|t| t.chars().count() >= 3 && !t.chars().any(|c| c.is_ascii_digit()))
+        .filter(|t| !STOP_WORDS.contains(&t.as_str()))
+        .filter(|t| seen.insert(t.clone()))
+        .collect()
+}
+
+fn normalize_merchant(merchant: &str) -> Option<String> {
+    let merchant = merchant.trim().to_lowercase();
+    (!merchant.is_empty()).then_some(merchant)
+}
+
+/// Who was paid: the merchant when known, otherwise the normalized
+/// description.
+fn payee(merchant: Option<&str>, tokens: &[String]) -> String {
+    merchant
+        .and_then(normalize_merchant)
+        .unwrap_or_else(|| tokens.join(" "))
+}
+
+/// Category frequencies per merchant, description word and amount bucket at
+/// each payee, learned from transactions the user has already categorized.
+#[derive(Default)]
+pub struct SuggestionModel {
+    merchants: HashMap<String, Counts>,
+    tokens: HashMap<String, Counts>,
+    amounts: HashMap<(String, AmountBucket), Counts>,
+}
+
+impl SuggestionModel {
+    /// Learns from the user's most recent categorized transactions.
+    pub async fn load(pool: &DbPool, user_id: Uuid) -> Result<Self, AppError> {
+        let history = sqlx::query!(
+            r#"SELECT t.merchant_name, t.description, t.amount, t.category_id as "category_id!"
+             FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1 AND t.category_id IS NOT NULL
+             ORDER BY t.date DESC
+             LIMIT $2"#,
+            user_id,
+            HISTORY_LIMIT
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut model = Self::default();
+
+        for t in history {
+            model.learn(
+                t.merchant_name.as_deref(),
+                &t.description,
+                t.amount,
+                t.category_id,
+            );
+        }
+
+        Ok(model)
+    }
+
+    pub fn learn(
+        &mut self,
+        merchant_name: Option<&str>,
+        description: &str,
+        amount: Decimal,
+        category_id: Uuid,
+    ) {
+        if let Some(merchant) = merchant_name.and_then(normalize_merchant) {
+            self.merchants.entry(merchant).or_default().add(category_id);
+        }
+
+        let tokens = tokens(description);
+
+        self.amounts
+            .entry((payee(merchant_name, &tokens), AmountBucket::of(amount)))
+            .or_default()
+            .add(category_id);
+
+        for token in tokens {
+            self.tokens.entry(token).or_default().add(category_id);
+        }
+    }
+
+    /// Up to three categories, most likely first. Each kind of evidence votes
+    /// with the share of past transactions in each category, shrunk towards
+    /// zero when there are few of them. The amount alone never produces a
+    /// suggestion.
+    pub fn suggest(
+        &self,
+        merchant_name: Option<&str>,
+        description: &str,
+        amount: Decimal,
+    ) -> Vec<Suggestion> {
+        let merchant = merchant_name
+            .and_then(normalize_merchant)
+            .and_then(|m| self.merchants.get(&m));
+
+        let tokens = tokens(description);
+        let amount = self
+            .amounts
+            .get(&(payee(merchant_name, &tokens), AmountBucket::of(amount)));
+
+        let mut token_counts = Counts::default();
+        for token in &tokens {
+            if let Some(counts) = self.tokens.get(token) {
+                for (category_id, count) in &counts.by_category {
+                    *token_counts.by_category.entry(*category_id).or_default() += count;
+                }
+                token_counts.total += counts.total;
+            }
+        }
+
+        if merchant.is_none() && token_counts.total == 0 {
+            return Vec::new();
+        }
+
+        let evidence = [
+            (MERCHANT_WEIGHT, merchant),
+            (TOKEN_WEIGHT, Some(&token_counts)),
+            (AMOUNT_WEIGHT, amount),
+        ];
+
+        let mut scores: HashMap<Uuid, f64> = HashMap::new();
+        let mut total_weight = 0.0;
+
+        for (weight, counts) in evidence {
+            let Some(counts) = counts.filter(|c| c.total > 0) else {
+                continue;
+            };
+
+            total_weight += weight;
+            for (category_id, count) in &counts.by_category {
+                *scores.entry(*category_id).or_default() +=
+                    weight * f64::from(*count) / f64::from(counts.total + 1);
+            }
+        }
+
+        let mut suggestions: Vec<Suggestion> = scores
+            .into_iter()
+            .map(|(category_id, score)| Suggestion {
+                category_id,
+                confidence: (score / total_weight * 100.0).round() / 100.0,
+            })
+            .filter(|s| s.confidence > 0.0)
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then(a.category_id.cmp(&b.category_id))
+        });
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::{db::DbPool, utils::AppError};
+
+/// How many days apart the two sides of a transfer may post.
+const MATCH_WINDOW_DAYS: i32 = 3;
+
+/// Links pairs of settled transactions between two of the user's accounts
+/// that have equal and opposite amounts in the same currency and post within
+/// a few days of each other. Each transaction joins at most one transfer, the
+/// closest pairs being linked first, and pairs the user has unlinked are left
+/// alone. Returns how many transfers were linked.
+pub async fn detect(pool: &DbPool, user_id: Uuid) -> Result<u64, AppError> {
+    let candidates = sqlx::query!(
+        "SELECT o.id as outflow_id, i.id as inflow_id
+         FROM transactions o
+         JOIN accounts oa ON o.account_id = oa.id
+         JOIN transactions i ON i.amount = -o.amount
+            AND i.account_id <> o.account_id
+            AND i.date BETWEEN o.date - $2::int AND o.date + $2::int
+         JOIN accounts ia ON i.account_id = ia.id
+         WHERE oa.user_id = $1 AND ia.user_id = $1
+         AND oa.currency = ia.currency
+         AND o.amount < 0
+         AND NOT o.pending AND NOT i.pending
+         AND NOT EXISTS (
+            SELECT 1 FROM transfers tr
+            WHERE (tr.status = 'linked' AND (
+                tr.outflow_transaction_id IN (o.id, i.id)
+                OR tr.inflow_transaction_id IN (o.id, i.id)
+            ))
+            OR (tr.outflow_transaction_id = o.id AND tr.inflow_transaction_id = i.id)
+         )
+         ORDER BY ABS(i.date - o.date), o.date, o.id, i.id",
+        user_id,
+        MATCH_WINDOW_DAYS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut used = HashSet::new();
+    let mut outflows = Vec::new();
+    let mut inflows = Vec::new();
+
+    for c in candidates {
+        if used.contains(&c.outflow_id) || used.contains(&c.inflow_id) {
+            continue;
+        }
+
+        used.insert(c.outflow_id);
+        used.insert(c.inflow_id);
+        outflows.push(c.outflow_id);
+        inflows.push(c.inflow_id);
+    }
+
+    // A concurrent run may have linked some of these already.
+    let linked = sqlx::query!(
+        "INSERT INTO transfers (user_id, outflow_transaction_id, inflow_transaction_id, source)
+         SELECT $1, p.outflow, p.inflow, 'detected'
+         FROM UNNEST($2::uuid[], $3::uuid[]) AS p(outflow, inflow)
+         ON CONFLICT DO NOTHING",
+        user_id,
+        &outflows,
+        &inflows
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(linked)
+}
@@ -0,0 +1,136 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::collections::HashMap;
+
+use super::AppError;
+
+const NONCE_LEN: usize = 12;
+
+/// A secret sealed with a one-off data key, which is itself wrapped by a
+/// master key. Both values are base64 of `nonce || ciphertext`.
+pub struct Sealed {
+    pub ciphertext: String,
+    pub data_key: String,
+    pub key_id: String,
+}
+
+/// The set of master keys available for unwrapping, plus the one used for
+/// new writes. Older keys stay in the ring until rotation has moved every row
+/// off them.
+pub struct KeyRing {
+    active_key_id: String,
+    keys: HashMap<String, Key<Aes256Gcm>>,
+}
+
+impl KeyRing {
+    /// Parses `id:base64key` pairs separated by commas; each key must decode to
+    /// 32 bytes.
+    pub fn new(keys: &str, active_key_id: &str) -> Result<Self, AppError> {
+        let mut parsed = HashMap::new();
+
+        for entry in keys.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, encoded) = entry
+                .split_once(':')
+                .ok_or_else(|| AppError::Internal(format!("Malformed key entry '{}'", entry)))?;
+
+            let bytes = STANDARD
+                .decode(encoded)
+                .map_err(|_| AppError::Internal(format!("Key '{}' is not valid base64", id)))?;
+
+            if bytes.len() != 32 {
+                return Err(AppError::Internal(format!("Key '{}' must be 32 bytes", id)));
+            }
+
+            parsed.insert(id.to_string(), *Key::<Aes256Gcm>::from_slice(&bytes));
+        }
+
+        if !parsed.contains_key(active_key_id) {
+            return Err(AppError::Internal(format!(
+                "Active key '{}' is not in the key ring",
+                active_key_id
+            )));
+        }
+
+        Ok(Self {
+            active_key_id: active_key_id.to_string(),
+            keys: parsed,
+        })
+    }
+
+    /// Reads the ring from `TOKEN_ENCRYPTION_KEYS` and `TOKEN_ENCRYPTION_KEY_ID`;
+    /// called once at startup.
+    pub fn from_env() -> Self {
+        let keys =
+            std::env::var("TOKEN_ENCRYPTION_KEYS").expect("TOKEN_ENCRYPTION_KEYS must be set");
+        let active_key_id =
+            std::env::var("TOKEN_ENCRYPTION_KEY_ID").expect("TOKEN_ENCRYPTION_KEY_ID must be set");
+
+        Self::new(&keys, &active_key_id).expect("TOKEN_ENCRYPTION_KEYS must be valid")
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<Sealed, AppError> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+
+        Ok(Sealed {
+            ciphertext: encrypt(&data_key, plaintext.as_bytes())?,
+            data_key: encrypt(self.key(&self.active_key_id)?, &data_key)?,
+            key_id: self.active_key_id.clone(),
+        })
+    }
+
+    pub fn open(&self, ciphertext: &str, data_key: &str, key_id: &str) -> Result<String, AppError> {
+        let data_key = decrypt(self.key(key_id)?, data_key)?;
+        let plaintext = decrypt(Key::<Aes256Gcm>::from_slice(&data_key), ciphertext)?;
+
+        String::from_utf8(plaintext).map_err(|_| AppError::Internal("Corrupt secret".to_string()))
+    }
+
+    /// Re-wraps a data key under the active master key, leaving the sealed
+    /// secret itself untouched.
+    pub fn rewrap(&self, data_key: &str, key_id: &str) -> Result<String, AppError> {
+        let data_key = decrypt(self.key(key_id)?, data_key)?;
+
+        encrypt(self.key(&self.active_key_id)?, &data_key)
+    }
+
+    fn key(&self, key_id: &str) -> Result<&Key<Aes256Gcm>, AppError> {
+        self.keys
+            .get(key_id)
+            .ok_or_else(|| AppError::Internal(format!("Unknown encryption key '{}'", key_id)))
+    }
+}
+
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &[u8]) -> Result<String, AppError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| AppError::Internal("Encryption failed".to_string()))?;
+
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+
+    Ok(STANDARD.encode(out))
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, encoded: &str) -> Result<Vec<u8>, AppError> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|_| AppError::Internal("Corrupt ciphertext".to_string()))?;
+
+    if bytes.len() < NONCE_LEN {
+        return Err(AppError::Internal("Corrupt ciphertext".to_string()));
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::Internal("Decryption failed".to_string()))
+}
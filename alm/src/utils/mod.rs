@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod crypto;
 
 use axum::{
     http::StatusCode,
@@ -7,12 +8,16 @@ use axum::{
 };
 use serde_json::json;
 
+use crate::plaid::PlaidError;
+
+#[derive(Debug)]
 pub enum AppError {
     Database(sqlx::Error),
     Unauthorized,
     BadRequest(String),
     NotFound,
     Internal(String),
+    Plaid(PlaidError),
 }
 
 impl IntoResponse for AppError {
@@ -23,6 +28,15 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_string()),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::Plaid(e) => {
+                let status = match e {
+                    PlaidError::ItemLoginRequired(_) => StatusCode::CONFLICT,
+                    PlaidError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+                    PlaidError::InstitutionUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::BAD_GATEWAY,
+                };
+                (status, e.to_string())
+            }
         };
 
         (status, Json(json!({ "error": message }))).into_response()
@@ -34,3 +48,9 @@ impl From<sqlx::Error> for AppError {
         AppError::Database(err)
     }
 }
+
+impl From<PlaidError> for AppError {
+    fn from(err: PlaidError) -> Self {
+        AppError::Plaid(err)
+    }
+}
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
     use super::super::common::TestContext;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -15,7 +17,7 @@ mod tests {
             ctx.test_user_id,
             "Test Checking",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -32,7 +34,7 @@ mod tests {
         .unwrap();
 
         assert_eq!(account.account_name, "Test Checking");
-        assert_eq!(account.balance, 1000.00);
+        assert_eq!(account.balance, dec!(1000.00));
 
         ctx.cleanup().await;
     }
@@ -51,7 +53,7 @@ mod tests {
             ctx.test_user_id,
             "Checking",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -65,7 +67,7 @@ mod tests {
             ctx.test_user_id,
             "Savings",
             "savings",
-            5000.00,
+            dec!(5000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -97,7 +99,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -123,4 +125,251 @@ mod tests {
 
         ctx.cleanup().await;
     }
+
+    #[tokio::test]
+    async fn test_manual_account_balance_from_entries_and_transactions() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type, balance)
+             VALUES ($1, $2, $3, $4, $5)",
+            account_id,
+            ctx.test_user_id,
+            "Wallet",
+            "cash",
+            dec!(80.00)
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let balance = || async {
+            sqlx::query_scalar!("SELECT balance FROM accounts WHERE id = $1", account_id)
+                .fetch_one(&ctx.pool)
+                .await
+                .unwrap()
+        };
+
+        for (day, amount) in [(5, dec!(-20.00)), (12, dec!(-15.50)), (20, dec!(40.00))] {
+            sqlx::query!(
+                "INSERT INTO transactions (account_id, date, amount, description)
+                 VALUES ($1, $2, $3, 'Cash')",
+                account_id,
+                date(day),
+                amount
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        // Without entries, a tracked balance keeps its value.
+        alm::balances::refresh(&ctx.pool, account_id).await.unwrap();
+        assert_eq!(balance().await, dec!(80.00));
+
+        sqlx::query!(
+            "INSERT INTO account_balance_entries (account_id, date, balance) VALUES ($1, $2, $3)",
+            account_id,
+            date(10),
+            dec!(100.00)
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        alm::balances::refresh(&ctx.pool, account_id).await.unwrap();
+        assert_eq!(balance().await, dec!(100.00));
+
+        // Derived: the entry plus the transactions after it.
+        sqlx::query!(
+            "UPDATE accounts SET balance_from_transactions = true WHERE id = $1",
+            account_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        alm::balances::refresh(&ctx.pool, account_id).await.unwrap();
+        assert_eq!(balance().await, dec!(124.50));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_backfill_balance_history() {
+        let ctx = TestContext::new().await;
+
+        let today = chrono::Utc::now().date_naive();
+        let days_ago = |n| today - chrono::Duration::days(n);
+
+        let mut accounts = Vec::new();
+        for (account_type, balance) in [("depository", dec!(500.00)), ("credit", dec!(120.00))] {
+            let id = Uuid::new_v4();
+            sqlx::query!(
+                "INSERT INTO accounts (id, user_id, plaid_account_id, account_name, account_type, balance)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                id,
+                ctx.test_user_id,
+                format!("acc-{}", id),
+                account_type,
+                account_type,
+                balance
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+            for (n, amount, pending) in [
+                (3, dec!(-50.00), false),
+                (1, dec!(-20.00), false),
+                (0, dec!(-5.00), true),
+            ] {
+                sqlx::query!(
+                    "INSERT INTO transactions (account_id, date, amount, description, pending)
+                     VALUES ($1, $2, $3, 'Purchase', $4)",
+                    id,
+                    days_ago(n),
+                    amount,
+                    pending
+                )
+                .execute(&ctx.pool)
+                .await
+                .unwrap();
+            }
+            accounts.push(id);
+        }
+        let (checking, card) = (accounts[0], accounts[1]);
+
+        // A balance synced two days ago is kept over the estimate.
+        alm::balances::history::record(
+            &ctx.pool,
+            checking,
+            days_ago(2),
+            dec!(480.00),
+            alm::balances::history::Source::Sync,
+        )
+        .await
+        .unwrap();
+
+        for id in [checking, card] {
+            alm::balances::history::backfill(&ctx.pool, id)
+                .await
+                .unwrap();
+        }
+
+        let pool = &ctx.pool;
+        let history = |account_id: Uuid| async move {
+            sqlx::query!(
+                "SELECT date, balance, source FROM account_balance_history
+                 WHERE account_id = $1 ORDER BY date",
+                account_id
+            )
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|h| (h.date, h.balance, h.source))
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            history(checking).await,
+            vec![
+                (days_ago(3), dec!(520.00), "backfill".to_string()),
+                (days_ago(2), dec!(480.00), "sync".to_string()),
+                (days_ago(1), dec!(500.00), "backfill".to_string()),
+            ]
+        );
+
+        // Balances are as of the end of each day; spending on a card raised
+        // what was owed, and today's pending charge is not counted yet.
+        assert_eq!(
+            history(card)
+                .await
+                .into_iter()
+                .map(|(_, balance, _)| balance)
+                .collect::<Vec<_>>(),
+            vec![dec!(100.00), dec!(100.00), dec!(120.00)]
+        );
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_archived_plaid_accounts_become_manual() {
+        let ctx = TestContext::new().await;
+
+        let linked_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts
+                (id, user_id, plaid_item_id, plaid_account_id, account_name, account_type, balance)
+             VALUES ($1, $2, 'item-archive', 'plaid-account-archive', 'Old Checking', 'depository', 500.00)",
+            linked_id,
+            ctx.test_user_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        sqlx::query!(
+            "INSERT INTO accounts (user_id, account_name, account_type, balance)
+             VALUES ($1, 'Wallet', 'cash', 100.00)",
+            ctx.test_user_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        // What removing the item with transactions=keep does.
+        sqlx::query!(
+            "UPDATE accounts SET plaid_item_id = NULL, plaid_account_id = NULL, archived_at = NOW()
+             WHERE user_id = $1 AND plaid_item_id = $2",
+            ctx.test_user_id,
+            "item-archive"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let net_worth = sqlx::query_scalar!(
+            "SELECT SUM(balance) FROM accounts WHERE user_id = $1 AND archived_at IS NULL",
+            ctx.test_user_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(net_worth, Some(dec!(100.00)));
+
+        // No longer linked, so its details and balance can be edited by hand.
+        let plaid_account_id = sqlx::query_scalar!(
+            "SELECT plaid_account_id FROM accounts WHERE id = $1",
+            linked_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(plaid_account_id, None);
+
+        sqlx::query!(
+            "INSERT INTO account_balance_entries (account_id, date, balance) VALUES ($1, $2, $3)",
+            linked_id,
+            NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+            dec!(0.00)
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        alm::balances::refresh(&ctx.pool, linked_id).await.unwrap();
+
+        let balance = sqlx::query_scalar!("SELECT balance FROM accounts WHERE id = $1", linked_id)
+            .fetch_one(&ctx.pool)
+            .await
+            .unwrap();
+        assert_eq!(balance, dec!(0.00));
+
+        ctx.cleanup().await;
+    }
 }
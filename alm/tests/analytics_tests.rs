@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
     use super::super::common::TestContext;
+    use alm::balances::Classification;
     use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -18,7 +21,7 @@ mod tests {
             ctx.test_user_id,
             "Checking",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -32,7 +35,7 @@ mod tests {
             ctx.test_user_id,
             "Savings",
             "savings",
-            5000.00,
+            dec!(5000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -47,8 +50,8 @@ mod tests {
         .await
         .unwrap();
 
-        let total: f64 = accounts.iter().map(|a| a.balance).sum();
-        assert_eq!(total, 6000.00);
+        let total: Decimal = accounts.iter().map(|a| a.balance).sum();
+        assert_eq!(total, dec!(6000.00));
 
         ctx.cleanup().await;
     }
@@ -65,7 +68,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -94,7 +97,7 @@ mod tests {
             Uuid::new_v4(),
             account_id,
             date,
-            -100.00,
+            dec!(-100.00),
             "Grocery Store",
             grocery_category,
             false
@@ -109,7 +112,7 @@ mod tests {
             Uuid::new_v4(),
             account_id,
             date,
-            -50.00,
+            dec!(-50.00),
             "Restaurant",
             dining_category,
             false
@@ -144,8 +147,251 @@ mod tests {
         .unwrap();
 
         assert_eq!(spending.len(), 2);
-        assert_eq!(spending[0].total.unwrap(), 100.00);
-        assert_eq!(spending[1].total.unwrap(), 50.00);
+        assert_eq!(spending[0].total.unwrap(), dec!(100.00));
+        assert_eq!(spending[1].total.unwrap(), dec!(50.00));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_large_sums_stay_exact() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type, balance, currency)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            account_id,
+            ctx.test_user_id,
+            "Test Account",
+            "checking",
+            dec!(0.00),
+            "USD"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        // 0.10 has no exact binary representation, so a thousand of them
+        // drift as f64 sums; a large balance on top exhausts f64's precision.
+        let mut amounts = vec![dec!(-0.10); 1000];
+        amounts.push(dec!(-1234567890123.45));
+
+        for amount in &amounts {
+            sqlx::query!(
+                "INSERT INTO transactions (id, account_id, date, amount, description, pending)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                Uuid::new_v4(),
+                account_id,
+                date,
+                amount,
+                "Purchase",
+                false
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        let total = sqlx::query_scalar!(
+            "SELECT COALESCE(SUM(ABS(t.amount)), 0) as total
+             FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1 AND t.amount < 0",
+            ctx.test_user_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap()
+        .unwrap_or_default();
+
+        let expected = dec!(1234567890223.45);
+        assert_eq!(total, expected);
+        assert_eq!(amounts.iter().map(|a| a.abs()).sum::<Decimal>(), expected);
+        assert_eq!(
+            serde_json::to_value(total).unwrap(),
+            serde_json::json!("1234567890223.45")
+        );
+
+        ctx.cleanup().await;
+    }
+
+    #[test]
+    fn test_classify_accounts() {
+        assert_eq!(
+            Classification::of("depository", None),
+            Classification::Asset
+        );
+        assert_eq!(
+            Classification::of("investment", None),
+            Classification::Asset
+        );
+        assert_eq!(
+            Classification::of("credit", None),
+            Classification::Liability
+        );
+        assert_eq!(Classification::of("loan", None), Classification::Liability);
+        // A loan the user made to someone else is owed to them.
+        assert_eq!(
+            Classification::of("loan", Some("asset")),
+            Classification::Asset
+        );
+        assert_eq!(
+            Classification::of("other", Some("liability")),
+            Classification::Liability
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classification_decides_how_spending_moves_a_balance() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type, balance_from_transactions)
+             VALUES ($1, $2, $3, $4, true)",
+            account_id,
+            ctx.test_user_id,
+            "Store Card",
+            "other"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        sqlx::query!(
+            "INSERT INTO transactions (account_id, date, amount, description)
+             VALUES ($1, '2024-01-15', $2, 'Purchase')",
+            account_id,
+            dec!(-60.00)
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let balance = || async {
+            alm::balances::refresh(&ctx.pool, account_id).await.unwrap();
+            sqlx::query_scalar!("SELECT balance FROM accounts WHERE id = $1", account_id)
+                .fetch_one(&ctx.pool)
+                .await
+                .unwrap()
+        };
+
+        assert_eq!(balance().await, dec!(-60.00));
+
+        sqlx::query!(
+            "UPDATE accounts SET classification = 'liability' WHERE id = $1",
+            account_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(balance().await, dec!(60.00));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_spending_buckets_follow_week_start_day() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        // Saturday 6, Sunday 7 and Monday 8 January 2024, then nothing until
+        // Tuesday 23.
+        for (day, amount) in [
+            (6, dec!(-10.00)),
+            (7, dec!(-20.00)),
+            (8, dec!(-40.00)),
+            (23, dec!(-5.00)),
+        ] {
+            sqlx::query!(
+                "INSERT INTO transactions (id, account_id, date, amount, description, pending)
+                 VALUES ($1, $2, $3, $4, $5, false)",
+                Uuid::new_v4(),
+                account_id,
+                NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                amount,
+                "Shop"
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        let weekly = |offset: i32| {
+            let pool = &ctx.pool;
+            let user_id = ctx.test_user_id;
+            async move {
+                sqlx::query!(
+                    "SELECT b.bucket as \"bucket!\", COALESCE(SUM(ABS(s.amount)), 0) as \"total!\"
+                     FROM (
+                        SELECT (b::date + $3::int) as bucket
+                        FROM generate_series(
+                            date_trunc('week', ($1::date - $3::int)::timestamp),
+                            ($2::date - $3::int)::timestamp,
+                            '1 week'::interval
+                        ) b
+                     ) b
+                     LEFT JOIN (
+                        SELECT date_trunc('week', (t.date - $3::int)::timestamp)::date + $3::int as bucket,
+                            t.amount
+                        FROM transaction_allocations t
+                        JOIN accounts a ON t.account_id = a.id
+                        WHERE a.user_id = $4 AND t.date >= $1 AND t.date <= $2 AND t.amount < 0
+                     ) s ON s.bucket = b.bucket
+                     GROUP BY b.bucket
+                     ORDER BY b.bucket",
+                    NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                    NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+                    offset,
+                    user_id
+                )
+                .fetch_all(pool)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.bucket.format("%m-%d").to_string(), r.total))
+                .collect::<Vec<_>>()
+            }
+        };
+
+        // ISO weeks start on Monday; empty weeks are zero.
+        assert_eq!(
+            weekly(0).await,
+            vec![
+                ("01-01".to_string(), dec!(30.00)),
+                ("01-08".to_string(), dec!(40.00)),
+                ("01-15".to_string(), dec!(0)),
+                ("01-22".to_string(), dec!(5.00)),
+                ("01-29".to_string(), dec!(0)),
+            ]
+        );
+
+        // Weeks starting on Sunday (week_start_day 7) begin before the range.
+        assert_eq!(
+            weekly(6).await,
+            vec![
+                ("12-31".to_string(), dec!(10.00)),
+                ("01-07".to_string(), dec!(60.00)),
+                ("01-14".to_string(), dec!(0)),
+                ("01-21".to_string(), dec!(5.00)),
+                ("01-28".to_string(), dec!(0)),
+            ]
+        );
 
         ctx.cleanup().await;
     }
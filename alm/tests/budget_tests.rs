@@ -2,6 +2,7 @@
 mod tests {
     use super::super::common::TestContext;
     use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -24,7 +25,7 @@ mod tests {
             budget_id,
             ctx.test_user_id,
             category_id,
-            500.00,
+            dec!(500.00),
             "monthly",
             start_date
         )
@@ -41,7 +42,7 @@ mod tests {
         .await
         .unwrap();
 
-        assert_eq!(budget.amount, 500.00);
+        assert_eq!(budget.amount, dec!(500.00));
         assert_eq!(budget.period, "monthly");
 
         ctx.cleanup().await;
@@ -68,7 +69,7 @@ mod tests {
             budget_id,
             ctx.test_user_id,
             category_id,
-            500.00,
+            dec!(500.00),
             "monthly",
             start_date,
             end_date
@@ -85,7 +86,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -98,7 +99,7 @@ mod tests {
             Uuid::new_v4(),
             account_id,
             NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
-            -150.00,
+            dec!(-150.00),
             "Grocery Shopping",
             category_id,
             false
@@ -124,9 +125,9 @@ mod tests {
         .fetch_one(&ctx.pool)
         .await
         .unwrap()
-        .unwrap_or(0.0);
+        .unwrap_or_default();
 
-        assert_eq!(spent, 150.00);
+        assert_eq!(spent, dec!(150.00));
 
         ctx.cleanup().await;
     }
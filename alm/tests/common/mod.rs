@@ -1,11 +1,17 @@
+pub mod plaid_mock;
+
+use alm::utils::crypto::KeyRing;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use uuid::Uuid;
 
+const TEST_KEYS: &str = "test:dGVzdC1rZXktdGVzdC1rZXktdGVzdC1rZXktdGVzdC0=";
+
 pub struct TestContext {
     pub pool: PgPool,
     pub test_user_id: Uuid,
     pub test_user_email: String,
     pub test_user_password: String,
+    pub keys: KeyRing,
 }
 
 impl TestContext {
@@ -45,6 +51,7 @@ impl TestContext {
             test_user_id,
             test_user_email,
             test_user_password: test_user_password.to_string(),
+            keys: KeyRing::new(TEST_KEYS, "test").unwrap(),
         }
     }
 
@@ -0,0 +1,103 @@
+use axum::{
+    extract::State,
+    http::{StatusCode, Uri},
+    Json, Router,
+};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct MockState {
+    responses: HashMap<String, VecDeque<(StatusCode, Value)>>,
+    requests: Vec<(String, Value)>,
+}
+
+/// A local stand-in for the Plaid API. Each endpoint replays its queued
+/// responses in order, repeating the last one once the queue runs dry.
+#[derive(Clone)]
+pub struct MockPlaid {
+    pub base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockPlaid {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let app = Router::new()
+            .fallback(handle)
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock Plaid server");
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self {
+            base_url: format!("http://{}", addr),
+            state,
+        }
+    }
+
+    pub fn respond(&self, path: &str, body: Value) {
+        self.respond_with_status(path, 200, body);
+    }
+
+    pub fn respond_with_status(&self, path: &str, status: u16, body: Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(path.to_string())
+            .or_default()
+            .push_back((StatusCode::from_u16(status).unwrap(), body));
+    }
+
+    pub fn requests(&self, path: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|(p, _)| p == path)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+
+    pub fn client(&self) -> alm::plaid::PlaidClient {
+        alm::plaid::PlaidClient::with_base_url(
+            "test_client_id".to_string(),
+            "test_secret".to_string(),
+            self.base_url.clone(),
+        )
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<Mutex<MockState>>>,
+    uri: Uri,
+    Json(body): Json<Value>,
+) -> (StatusCode, Json<Value>) {
+    let path = uri.path().to_string();
+    let mut state = state.lock().unwrap();
+
+    state.requests.push((path.clone(), body));
+
+    let queue = state.responses.entry(path).or_default();
+    let response = if queue.len() > 1 {
+        queue.pop_front()
+    } else {
+        queue.front().cloned()
+    };
+
+    let (status, body) = response.unwrap_or((StatusCode::NOT_FOUND, Value::Null));
+
+    (status, Json(body))
+}
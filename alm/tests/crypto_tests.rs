@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::TestContext;
+    use alm::utils::crypto::KeyRing;
+    use uuid::Uuid;
+
+    const OLD_KEY: &str = "old:MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=";
+    const NEW_KEY: &str = "new:YWJjZGVmZ2hpamtsbW5vcHFyc3R1dnd4eXphYmNkZWY=";
+
+    #[test]
+    fn test_seal_and_open_access_token() {
+        let keys = KeyRing::new(OLD_KEY, "old").unwrap();
+
+        let sealed = keys.seal("access-sandbox-abc").unwrap();
+
+        assert_eq!(sealed.key_id, "old");
+        assert!(!sealed.ciphertext.contains("access-sandbox-abc"));
+
+        let opened = keys
+            .open(&sealed.ciphertext, &sealed.data_key, &sealed.key_id)
+            .unwrap();
+
+        assert_eq!(opened, "access-sandbox-abc");
+    }
+
+    #[test]
+    fn test_open_with_unknown_key_fails() {
+        let old = KeyRing::new(OLD_KEY, "old").unwrap();
+        let new = KeyRing::new(NEW_KEY, "new").unwrap();
+
+        let sealed = old.seal("access-sandbox-abc").unwrap();
+
+        assert!(new
+            .open(&sealed.ciphertext, &sealed.data_key, &sealed.key_id)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rewrap_data_key() {
+        let old = KeyRing::new(OLD_KEY, "old").unwrap();
+        let ring = KeyRing::new(&format!("{},{}", OLD_KEY, NEW_KEY), "new").unwrap();
+
+        let sealed = old.seal("access-sandbox-abc").unwrap();
+        let data_key = ring.rewrap(&sealed.data_key, &sealed.key_id).unwrap();
+
+        let opened = ring.open(&sealed.ciphertext, &data_key, "new").unwrap();
+
+        assert_eq!(opened, "access-sandbox-abc");
+    }
+
+    #[test]
+    fn test_reject_malformed_keys() {
+        assert!(KeyRing::new("old:c2hvcnQ=", "old").is_err());
+        assert!(KeyRing::new(OLD_KEY, "missing").is_err());
+        assert!(KeyRing::new("no-separator", "no-separator").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_access_tokens() {
+        let ctx = TestContext::new().await;
+
+        let legacy_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            legacy_id,
+            ctx.test_user_id,
+            "access-sandbox-legacy",
+            format!("item-{}", legacy_id),
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let sealed = KeyRing::new(OLD_KEY, "old")
+            .unwrap()
+            .seal("access-sandbox-old")
+            .unwrap();
+        let sealed_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, encrypted_data_key, encryption_key_id, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            sealed_id,
+            ctx.test_user_id,
+            sealed.ciphertext,
+            sealed.data_key,
+            sealed.key_id,
+            format!("item-{}", sealed_id),
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let keys = KeyRing::new(&format!("{},{}", OLD_KEY, NEW_KEY), "new").unwrap();
+        let result = alm::plaid::tokens::rotate_access_tokens(&ctx.pool, &keys).await;
+        assert!(result.is_ok());
+
+        let ring = KeyRing::new(NEW_KEY, "new").unwrap();
+
+        for (id, expected) in [
+            (legacy_id, "access-sandbox-legacy"),
+            (sealed_id, "access-sandbox-old"),
+        ] {
+            let row = sqlx::query!(
+                "SELECT plaid_access_token, encrypted_data_key, encryption_key_id FROM plaid_items WHERE id = $1",
+                id
+            )
+            .fetch_one(&ctx.pool)
+            .await
+            .unwrap();
+
+            assert_eq!(row.encryption_key_id.as_deref(), Some("new"));
+
+            let opened = ring
+                .open(
+                    &row.plaid_access_token,
+                    &row.encrypted_data_key.unwrap(),
+                    "new",
+                )
+                .unwrap();
+
+            assert_eq!(opened, expected);
+        }
+
+        ctx.cleanup().await;
+    }
+}
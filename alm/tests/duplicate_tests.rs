@@ -0,0 +1,361 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::TestContext;
+    use alm::{
+        duplicates::{self, similarity},
+        imports::{self, StatementFormat},
+    };
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    async fn transaction(
+        ctx: &TestContext,
+        account_id: Uuid,
+        day: u32,
+        amount: Decimal,
+        description: &str,
+        plaid_transaction_id: Option<&str>,
+        pending: bool,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO transactions (id, account_id, plaid_transaction_id, date, amount, description, pending)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            id,
+            account_id,
+            plaid_transaction_id.map(|p| format!("{}-{}", p, id)),
+            NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            amount,
+            description,
+            pending
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_description_similarity() {
+        assert_eq!(similarity("Whole Foods", "WHOLE FOODS"), 1.0);
+        assert!(similarity("Starbucks", "STARBUCKS #1234") > 0.5);
+        assert!(similarity("AMAZON MKTP US", "AMAZON MKTP US*2K4L") > 0.8);
+        assert!(similarity("Rent", "Groceries") < 0.2);
+        assert_eq!(similarity("", "Rent"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_find_and_merge_duplicates() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let dining = sqlx::query_scalar!(
+            "SELECT id FROM categories WHERE name = 'Dining Out' AND is_default = true LIMIT 1"
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        // Entered by hand, then imported from Plaid a day later.
+        let manual = transaction(&ctx, account_id, 10, dec!(-4.75), "Starbucks", None, false).await;
+        sqlx::query!(
+            "UPDATE transactions SET category_id = $2, notes = 'Meeting with Sam' WHERE id = $1",
+            manual,
+            dining
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+        let imported = transaction(
+            &ctx,
+            account_id,
+            11,
+            dec!(-4.75),
+            "STARBUCKS STORE 1234",
+            Some("txn"),
+            false,
+        )
+        .await;
+
+        // A pending charge left behind after it posted.
+        let pending = transaction(
+            &ctx,
+            account_id,
+            3,
+            dec!(-25.00),
+            "AMAZON MKTP US",
+            Some("txn"),
+            true,
+        )
+        .await;
+        let posted = transaction(
+            &ctx,
+            account_id,
+            5,
+            dec!(-25.00),
+            "AMAZON MKTP US*2K4L",
+            Some("txn"),
+            false,
+        )
+        .await;
+
+        // Two settled purchases, a different payee, and one too far apart.
+        transaction(
+            &ctx,
+            account_id,
+            20,
+            dec!(-40.00),
+            "SHELL OIL",
+            Some("txn"),
+            false,
+        )
+        .await;
+        transaction(
+            &ctx,
+            account_id,
+            20,
+            dec!(-40.00),
+            "SHELL OIL",
+            Some("txn"),
+            false,
+        )
+        .await;
+        transaction(&ctx, account_id, 1, dec!(-1500.00), "Rent", None, false).await;
+        transaction(
+            &ctx,
+            account_id,
+            2,
+            dec!(-1500.00),
+            "Groceries",
+            None,
+            false,
+        )
+        .await;
+        transaction(&ctx, account_id, 22, dec!(-9.99), "Spotify", None, false).await;
+        transaction(
+            &ctx,
+            account_id,
+            30,
+            dec!(-9.99),
+            "SPOTIFY USA",
+            Some("txn"),
+            false,
+        )
+        .await;
+
+        let pairs = duplicates::find(&ctx.pool, ctx.test_user_id).await.unwrap();
+        let found: Vec<(Uuid, Uuid)> = pairs.iter().map(|p| (p.keep.id, p.duplicate.id)).collect();
+        assert_eq!(found, vec![(manual, imported), (posted, pending)]);
+
+        let merged = duplicates::merge(&ctx.pool, ctx.test_user_id, manual, imported)
+            .await
+            .unwrap();
+        assert_eq!(merged.category_id, Some(dining));
+        assert_eq!(merged.notes.as_deref(), Some("Meeting with Sam"));
+        assert_eq!(merged.description, "Starbucks");
+        assert_eq!(merged.date, NaiveDate::from_ymd_opt(2024, 1, 11).unwrap());
+        assert_eq!(
+            merged.plaid_transaction_id,
+            Some(format!("txn-{}", imported))
+        );
+
+        // The pending copy is folded into the posted one, keeping its Plaid id.
+        let merged = duplicates::merge(&ctx.pool, ctx.test_user_id, posted, pending)
+            .await
+            .unwrap();
+        assert!(!merged.pending);
+        assert_eq!(merged.plaid_transaction_id, Some(format!("txn-{}", posted)));
+
+        let remaining = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transactions WHERE id IN ($1, $2)",
+            imported,
+            pending
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, Some(0));
+
+        assert!(duplicates::find(&ctx.pool, ctx.test_user_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_merged_statement_lines_are_not_imported_again() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Credit Union Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let statement = "<OFX>
+<BANKTRANLIST>
+<STMTTRN>
+<DTPOSTED>20240115
+<TRNAMT>-42.17
+<FITID>2024011501
+<NAME>CORNER GROCERY
+</STMTTRN>
+</BANKTRANLIST>
+</OFX>
+";
+        let import = || {
+            imports::import(
+                &ctx.pool,
+                ctx.test_user_id,
+                account_id,
+                statement,
+                Some(StatementFormat::Ofx),
+                true,
+            )
+        };
+
+        let manual = transaction(
+            &ctx,
+            account_id,
+            14,
+            dec!(-42.17),
+            "Corner Grocery",
+            None,
+            false,
+        )
+        .await;
+        assert_eq!(import().await.unwrap().transactions.len(), 1);
+        let imported = sqlx::query_scalar!(
+            "SELECT id FROM transactions WHERE account_id = $1 AND import_id = 'fitid:2024011501'",
+            account_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        let merged = duplicates::merge(&ctx.pool, ctx.test_user_id, manual, imported)
+            .await
+            .unwrap();
+        assert_eq!(merged.import_id.as_deref(), Some("fitid:2024011501"));
+
+        let again = import().await.unwrap();
+        assert_eq!(again.duplicates, 1);
+        assert!(again.transactions.is_empty());
+
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transactions WHERE account_id = $1",
+            account_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(count, Some(1));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_merging_two_imported_copies_keeps_both_import_ids() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Credit Union Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        // The same purchase downloaded twice, under different FITIDs.
+        let statement = |fitid: &str| {
+            format!(
+                "<OFX>
+<BANKTRANLIST>
+<STMTTRN>
+<DTPOSTED>20240115
+<TRNAMT>-42.17
+<FITID>{}
+<NAME>CORNER GROCERY
+</STMTTRN>
+</BANKTRANLIST>
+</OFX>
+",
+                fitid
+            )
+        };
+        let pool = &ctx.pool;
+        let user_id = ctx.test_user_id;
+        let import = |content: String| async move {
+            imports::import(
+                pool,
+                user_id,
+                account_id,
+                &content,
+                Some(StatementFormat::Ofx),
+                true,
+            )
+            .await
+            .unwrap()
+        };
+        let imported = |import_id: &'static str| {
+            sqlx::query_scalar!(
+                "SELECT id FROM transactions WHERE account_id = $1 AND import_id = $2",
+                account_id,
+                import_id
+            )
+            .fetch_one(&ctx.pool)
+        };
+
+        import(statement("A1")).await;
+        import(statement("B1")).await;
+        let first = imported("fitid:A1").await.unwrap();
+        let second = imported("fitid:B1").await.unwrap();
+
+        let merged = duplicates::merge(&ctx.pool, ctx.test_user_id, first, second)
+            .await
+            .unwrap();
+        assert_eq!(merged.import_id.as_deref(), Some("fitid:A1"));
+
+        for fitid in ["A1", "B1"] {
+            let again = import(statement(fitid)).await;
+            assert_eq!(again.duplicates, 1);
+            assert!(again.transactions.is_empty());
+        }
+
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transactions WHERE account_id = $1",
+            account_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(count, Some(1));
+
+        ctx.cleanup().await;
+    }
+}
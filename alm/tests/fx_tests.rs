@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::TestContext;
+    use alm::fx::{import, FxRates, MixedTotal};
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_ecb_history() {
+        let csv = "Date,USD,JPY,CYP,GBP,\n\
+                   2024-01-16,1.0877,160.65,N/A,0.8593,\n\
+                   2024-01-15,1.0945,160.12,N/A,0.8612,\n";
+
+        let rates = import::parse(csv).unwrap();
+
+        assert_eq!(rates.len(), 6);
+        assert_eq!(rates[0].base_currency, "EUR");
+        assert_eq!(rates[0].quote_currency, "USD");
+        assert_eq!(rates[0].rate_date, date(2024, 1, 16));
+        assert_eq!(rates[0].rate, dec!(1.0877));
+        assert!(rates.iter().all(|r| r.quote_currency != "CYP"));
+    }
+
+    #[test]
+    fn test_parse_ecb_daily() {
+        let csv = "Date, USD, JPY, \n15 January 2024, 1.0945, 160.12, \n";
+
+        let rates = import::parse(csv).unwrap();
+
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[1].quote_currency, "JPY");
+        assert_eq!(rates[1].rate_date, date(2024, 1, 15));
+        assert_eq!(rates[1].rate, dec!(160.12));
+    }
+
+    #[test]
+    fn test_parse_long_format() {
+        let csv = "date,base,quote,rate\n2024-01-15,usd,cad,1.3450\n";
+
+        let rates = import::parse(csv).unwrap();
+
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].base_currency, "USD");
+        assert_eq!(rates[0].quote_currency, "CAD");
+        assert_eq!(rates[0].rate, dec!(1.3450));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_rates() {
+        assert!(import::parse("Date,USD\n2024-01-15,abc\n").is_err());
+        assert!(import::parse("Date,USD\n2024-01-15,0\n").is_err());
+        assert!(import::parse("Date,USD\nyesterday,1.09\n").is_err());
+    }
+
+    #[test]
+    fn test_cross_and_inverse_rates() {
+        let mut rates = FxRates::default();
+        rates.insert("EUR", "USD", date(2024, 1, 15), dec!(1.25));
+        rates.insert("EUR", "GBP", date(2024, 1, 15), dec!(0.80));
+
+        let day = date(2024, 1, 15);
+
+        assert_eq!(rates.rate("USD", "USD", day), Some(dec!(1)));
+        assert_eq!(rates.rate("EUR", "USD", day), Some(dec!(1.25)));
+        assert_eq!(rates.rate("USD", "EUR", day), Some(dec!(0.8)));
+        assert_eq!(rates.rate("USD", "GBP", day), Some(dec!(0.64)));
+        assert_eq!(
+            rates.convert(dec!(100.00), "GBP", "USD", day).unwrap(),
+            dec!(156.25)
+        );
+        assert!(rates.rate("USD", "JPY", day).is_none());
+    }
+
+    #[test]
+    fn test_rate_falls_back_to_previous_fixing() {
+        let mut rates = FxRates::default();
+        rates.insert("EUR", "USD", date(2024, 1, 12), dec!(1.10));
+        rates.insert("EUR", "USD", date(2024, 1, 15), dec!(1.20));
+
+        // Saturday uses Friday's rate; nothing is known before the first fixing.
+        assert_eq!(
+            rates.rate("EUR", "USD", date(2024, 1, 13)),
+            Some(dec!(1.10))
+        );
+        assert_eq!(
+            rates.rate("EUR", "USD", date(2024, 1, 15)),
+            Some(dec!(1.20))
+        );
+        assert!(rates.rate("EUR", "USD", date(2024, 1, 11)).is_none());
+    }
+
+    #[test]
+    fn test_mixed_total_keeps_original_amounts() {
+        let mut rates = FxRates::default();
+        rates.insert("EUR", "USD", date(2024, 1, 15), dec!(1.10));
+
+        let mut total = MixedTotal::default();
+        let day = date(2024, 1, 15);
+        total.add(&rates, dec!(10.00), "USD", "USD", day).unwrap();
+        total.add(&rates, dec!(20.00), "EUR", "USD", day).unwrap();
+        total.add(&rates, dec!(5.00), "EUR", "USD", day).unwrap();
+
+        assert_eq!(total.converted, dec!(37.50));
+
+        let original = total.original();
+        assert_eq!(original.len(), 2);
+        assert_eq!(original[0].currency, "EUR");
+        assert_eq!(original[0].amount, dec!(25.00));
+        assert_eq!(original[1].currency, "USD");
+        assert_eq!(original[1].amount, dec!(10.00));
+    }
+
+    #[tokio::test]
+    async fn test_store_and_load_rates() {
+        let ctx = TestContext::new().await;
+
+        // A made-up pair keeps this test independent of any loaded ECB data.
+        let csv = "date,base,quote,rate\n\
+                   2023-12-29,XTS,XXX,2.0\n\
+                   2024-01-02,XTS,XXX,2.5\n\
+                   2024-03-01,XTS,XXX,3.0\n";
+        let parsed = import::parse(csv).unwrap();
+
+        assert_eq!(import::store(&ctx.pool, &parsed).await.unwrap(), 3);
+        // Reloading the same file replaces rather than duplicates.
+        assert_eq!(import::store(&ctx.pool, &parsed).await.unwrap(), 3);
+
+        let rates = FxRates::load(
+            &ctx.pool,
+            &["XXX".to_string()],
+            date(2024, 1, 1),
+            date(2024, 1, 31),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(rates.rate("XTS", "XXX", date(2024, 1, 1)), Some(dec!(2.0)));
+        assert_eq!(rates.rate("XTS", "XXX", date(2024, 1, 31)), Some(dec!(2.5)));
+
+        let currency = alm::fx::reporting_currency(&ctx.pool, ctx.test_user_id)
+            .await
+            .unwrap();
+        assert_eq!(currency, "USD");
+
+        sqlx::query!("DELETE FROM fx_rates WHERE base_currency = 'XTS'")
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+        ctx.cleanup().await;
+    }
+}
@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::TestContext;
+    use alm::imports::{self, ofx, StatementFormat};
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    const OFX_SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>USD
+<BANKTRANLIST>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240115120000[-5:EST]
+<TRNAMT>-42.17
+<FITID>2024011501
+<NAME>CORNER GROCERY
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20240116
+<TRNAMT>1500.00
+<FITID>2024011602
+<NAME>ACME PAYROLL
+<MEMO>Salary
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>2024-01-17
+<TRNAMT>-3.00
+<FITID>2024011703
+<NAME>BAD DATE
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+    async fn manual_account(ctx: &TestContext) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            id,
+            ctx.test_user_id,
+            "Credit Union Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn imported_count(ctx: &TestContext, account_id: Uuid) -> Option<i64> {
+        sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transactions WHERE account_id = $1",
+            account_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_ofx() {
+        assert_eq!(StatementFormat::detect(OFX_SGML), StatementFormat::Ofx);
+        assert_eq!(
+            StatementFormat::detect("Date,Amount\n"),
+            StatementFormat::Csv
+        );
+
+        let statement = ofx::parse(OFX_SGML);
+
+        assert_eq!(statement.lines.len(), 2);
+        assert_eq!(statement.lines[0].import_id, "fitid:2024011501");
+        assert_eq!(
+            statement.lines[0].date,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()
+        );
+        assert_eq!(statement.lines[0].amount, dec!(-42.17));
+        assert_eq!(statement.lines[0].description, "CORNER GROCERY");
+        assert_eq!(statement.lines[1].amount, dec!(1500.00));
+
+        assert_eq!(statement.errors.len(), 1);
+        assert_eq!(statement.errors[0].line, 24);
+
+        // OFX 2 closes every element.
+        let xml = "<OFX><STMTTRN><DTPOSTED>20240201</DTPOSTED><TRNAMT>-9,99</TRNAMT>\
+                   <NAME>Cafe &amp; Bar</NAME></STMTTRN></OFX>";
+        let statement = ofx::parse(xml);
+        assert_eq!(statement.lines[0].amount, dec!(-9.99));
+        assert!(statement.lines[0].import_id.starts_with("hash:"));
+    }
+
+    #[tokio::test]
+    async fn test_import_csv_previews_then_commits() {
+        let ctx = TestContext::new().await;
+        let account_id = manual_account(&ctx).await;
+
+        sqlx::query!(
+            "INSERT INTO import_profiles
+                (account_id, date_column, date_format, description_column, debit_column, credit_column)
+             VALUES ($1, 0, '%m/%d/%Y', 1, 2, 3)",
+            account_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let january = "Date,Description,Debit,Credit\r
+01/05/2024,\"Hardware Store, Main St\",\"$1,024.50\",\r
+01/06/2024,Coffee,4.75,\r
+01/06/2024,Coffee,4.75,\r
+01/07/2024,Refund,,12.00\r
+Total,,1033.00,12.00\r
+";
+
+        let preview = imports::import(
+            &ctx.pool,
+            ctx.test_user_id,
+            account_id,
+            january,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(preview.format, StatementFormat::Csv);
+        assert!(!preview.committed);
+        assert_eq!(preview.transactions.len(), 4);
+        assert_eq!(
+            preview.transactions[0].description,
+            "Hardware Store, Main St"
+        );
+        assert_eq!(preview.transactions[0].amount, dec!(-1024.50));
+        assert_eq!(preview.transactions[3].amount, dec!(12.00));
+        assert_eq!(preview.errors.len(), 1);
+        assert_eq!(preview.errors[0].line, 6);
+        assert_eq!(imported_count(&ctx, account_id).await, Some(0));
+
+        let committed =
+            imports::import(&ctx.pool, ctx.test_user_id, account_id, january, None, true)
+                .await
+                .unwrap();
+        assert_eq!(committed.transactions.len(), 4);
+        assert_eq!(imported_count(&ctx, account_id).await, Some(4));
+
+        // An overlapping download only adds what is new, both coffees included.
+        let overlapping = "Date,Description,Debit,Credit
+01/06/2024,Coffee,4.75,
+01/06/2024,Coffee,4.75,
+01/07/2024,Refund,,12.00
+01/08/2024,Coffee,4.75,
+";
+        let again = imports::import(
+            &ctx.pool,
+            ctx.test_user_id,
+            account_id,
+            overlapping,
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again.duplicates, 3);
+        assert_eq!(again.transactions.len(), 1);
+        assert_eq!(imported_count(&ctx, account_id).await, Some(5));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_import_ofx_skips_known_fitids() {
+        let ctx = TestContext::new().await;
+        let account_id = manual_account(&ctx).await;
+
+        // OFX needs no profile.
+        let first = imports::import(
+            &ctx.pool,
+            ctx.test_user_id,
+            account_id,
+            OFX_SGML,
+            Some(StatementFormat::Qfx),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.transactions.len(), 2);
+
+        let second = imports::import(
+            &ctx.pool,
+            ctx.test_user_id,
+            account_id,
+            OFX_SGML,
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.transactions.len(), 0);
+        assert_eq!(second.duplicates, 2);
+        assert_eq!(imported_count(&ctx, account_id).await, Some(2));
+
+        // CSV needs a saved column mapping first.
+        assert!(imports::import(
+            &ctx.pool,
+            ctx.test_user_id,
+            account_id,
+            "Date,Amount\n2024-01-01,5.00\n",
+            None,
+            false,
+        )
+        .await
+        .is_err());
+
+        ctx.cleanup().await;
+    }
+}
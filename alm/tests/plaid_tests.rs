@@ -0,0 +1,872 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::{plaid_mock::MockPlaid, TestContext};
+    use alm::plaid::PlaidError;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_create_link_token() {
+        let mock = MockPlaid::start().await;
+        mock.respond(
+            "/link/token/create",
+            json!({ "link_token": "link-sandbox-123", "expiration": "2024-01-01T00:00:00Z" }),
+        );
+
+        let user_id = Uuid::new_v4();
+        let link_token = mock
+            .client()
+            .create_link_token(user_id.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(link_token, "link-sandbox-123");
+
+        let requests = mock.requests("/link/token/create");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["client_id"], "test_client_id");
+        assert_eq!(requests[0]["user"]["client_user_id"], user_id.to_string());
+        assert_eq!(requests[0]["products"], json!(["transactions"]));
+    }
+
+    #[tokio::test]
+    async fn test_create_update_link_token() {
+        let mock = MockPlaid::start().await;
+        mock.respond(
+            "/link/token/create",
+            json!({ "link_token": "link-sandbox-update", "expiration": "2024-01-01T00:00:00Z" }),
+        );
+
+        let link_token = mock
+            .client()
+            .create_update_link_token(Uuid::new_v4().to_string(), "access-sandbox-abc")
+            .await
+            .unwrap();
+
+        assert_eq!(link_token, "link-sandbox-update");
+
+        let request = &mock.requests("/link/token/create")[0];
+        assert_eq!(request["access_token"], "access-sandbox-abc");
+        assert!(request.get("products").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exchange_public_token() {
+        let mock = MockPlaid::start().await;
+        mock.respond(
+            "/item/public_token/exchange",
+            json!({ "access_token": "access-sandbox-abc", "item_id": "item-1" }),
+        );
+
+        let (access_token, item_id) = mock
+            .client()
+            .exchange_public_token("public-sandbox-xyz")
+            .await
+            .unwrap();
+
+        assert_eq!(access_token, "access-sandbox-abc");
+        assert_eq!(item_id, "item-1");
+        assert_eq!(
+            mock.requests("/item/public_token/exchange")[0]["public_token"],
+            "public-sandbox-xyz"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_item() {
+        let mock = MockPlaid::start().await;
+        mock.respond("/item/remove", json!({ "request_id": "req-remove" }));
+
+        mock.client()
+            .remove_item("access-sandbox-abc")
+            .await
+            .unwrap();
+
+        let request = &mock.requests("/item/remove")[0];
+        assert_eq!(request["access_token"], "access-sandbox-abc");
+    }
+
+    fn institution() -> serde_json::Value {
+        json!({
+            "institution": {
+                "institution_id": "ins_109508",
+                "name": "First Platypus Bank",
+                "logo": "iVBORw0KGgo=",
+                "primary_color": "#1f1f1f",
+                "url": "https://plaid.com",
+                "products": ["transactions"],
+                "country_codes": ["US"]
+            },
+            "request_id": "req-ins"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_institution() {
+        let mock = MockPlaid::start().await;
+        mock.respond("/institutions/get_by_id", institution());
+
+        let info = mock.client().get_institution("ins_109508").await.unwrap();
+
+        assert_eq!(info.name, "First Platypus Bank");
+        assert_eq!(info.logo.as_deref(), Some("iVBORw0KGgo="));
+        assert_eq!(info.primary_color.as_deref(), Some("#1f1f1f"));
+
+        let request = &mock.requests("/institutions/get_by_id")[0];
+        assert_eq!(request["institution_id"], "ins_109508");
+        assert_eq!(request["options"]["include_optional_metadata"], true);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_institution_is_cached() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+        mock.respond("/institutions/get_by_id", institution());
+
+        sqlx::query!("DELETE FROM institutions WHERE institution_id = 'ins_109508'")
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+        let plaid = mock.client();
+        let first = alm::plaid::institutions::resolve(&ctx.pool, &plaid, "ins_109508")
+            .await
+            .unwrap();
+        let second = alm::plaid::institutions::resolve(&ctx.pool, &plaid, "ins_109508")
+            .await
+            .unwrap();
+
+        assert_eq!(first.name, "First Platypus Bank");
+        assert_eq!(second.name, "First Platypus Bank");
+        assert_eq!(mock.requests("/institutions/get_by_id").len(), 1);
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts() {
+        let mock = MockPlaid::start().await;
+        mock.respond(
+            "/accounts/get",
+            json!({
+                "accounts": [
+                    {
+                        "account_id": "acc-checking",
+                        "name": "Plaid Checking",
+                        "official_name": "Plaid Gold Standard 0% Interest Checking",
+                        "type": "depository",
+                        "subtype": "checking",
+                        "mask": "0000",
+                        "balances": {
+                            "current": 110.5,
+                            "available": 100.0,
+                            "limit": null,
+                            "iso_currency_code": "USD"
+                        }
+                    },
+                    {
+                        "account_id": "acc-credit",
+                        "name": "Plaid Credit Card",
+                        "type": "credit",
+                        "subtype": "credit card",
+                        "mask": "3333",
+                        "balances": {
+                            "current": null,
+                            "limit": 2000.0,
+                            "iso_currency_code": null,
+                            "unofficial_currency_code": "CAD"
+                        }
+                    }
+                ]
+            }),
+        );
+
+        let accounts = mock
+            .client()
+            .get_accounts("access-sandbox-abc")
+            .await
+            .unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].account_id, "acc-checking");
+        assert_eq!(accounts[0].account_type, "depository");
+        assert_eq!(accounts[0].subtype.as_deref(), Some("checking"));
+        assert_eq!(accounts[0].mask.as_deref(), Some("0000"));
+        assert_eq!(accounts[0].balance, dec!(110.5));
+        assert_eq!(accounts[0].available_balance, Some(dec!(100.0)));
+        assert_eq!(accounts[0].credit_limit, None);
+        assert_eq!(accounts[0].currency, "USD");
+        assert_eq!(accounts[1].balance, Decimal::ZERO);
+        assert_eq!(accounts[1].credit_limit, Some(dec!(2000.0)));
+        assert_eq!(accounts[1].currency, "CAD");
+        assert_eq!(
+            mock.requests("/accounts/get")[0]["access_token"],
+            "access-sandbox-abc"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_plaid_item_with_accounts() {
+        let ctx = TestContext::new().await;
+
+        let item_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            "item-1",
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, account_type, balance, currency)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            Uuid::new_v4(),
+            ctx.test_user_id,
+            "acc-checking",
+            "item-1",
+            "Plaid Checking",
+            "depository",
+            dec!(110.50),
+            "USD"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let linked = sqlx::query!(
+            "SELECT a.account_name, p.status
+             FROM accounts a
+             JOIN plaid_items p ON a.plaid_item_id = p.plaid_item_id
+             WHERE p.id = $1",
+            item_id
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].account_name, "Plaid Checking");
+        assert_eq!(linked[0].status, "active");
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_transactions_sync_page() {
+        let mock = MockPlaid::start().await;
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [{
+                    "transaction_id": "txn-1",
+                    "account_id": "acc-checking",
+                    "amount": 12.34,
+                    "date": "2024-01-15",
+                    "name": "Coffee Shop",
+                    "merchant_name": "Blue Bottle",
+                    "pending": false,
+                    "personal_finance_category": {
+                        "primary": "FOOD_AND_DRINK",
+                        "detailed": "FOOD_AND_DRINK_COFFEE",
+                        "confidence_level": "VERY_HIGH"
+                    }
+                }],
+                "modified": [],
+                "removed": [{ "transaction_id": "txn-0" }],
+                "next_cursor": "cursor-2",
+                "has_more": false
+            }),
+        );
+
+        let page = mock
+            .client()
+            .transactions_sync("access-sandbox-abc", Some("cursor-1".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(page.added.len(), 1);
+        assert_eq!(page.added[0].transaction_id, "txn-1");
+        assert_eq!(page.added[0].amount, dec!(12.34));
+        assert_eq!(page.added[0].merchant_name.as_deref(), Some("Blue Bottle"));
+        let category = page.added[0].personal_finance_category.as_ref().unwrap();
+        assert_eq!(category.primary, "FOOD_AND_DRINK");
+        assert_eq!(category.detailed, "FOOD_AND_DRINK_COFFEE");
+        assert_eq!(page.removed, vec!["txn-0".to_string()]);
+        assert_eq!(page.next_cursor, "cursor-2");
+        assert!(!page.has_more);
+        assert_eq!(mock.requests("/transactions/sync")[0]["cursor"], "cursor-1");
+    }
+
+    #[tokio::test]
+    async fn test_sync_transactions_applies_pages_and_stores_cursor() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+
+        let item_id = Uuid::new_v4();
+        let plaid_item_id = format!("item-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            plaid_item_id,
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let plaid_account_id = format!("acc-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, account_type)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            Uuid::new_v4(),
+            ctx.test_user_id,
+            plaid_account_id,
+            plaid_item_id,
+            "Plaid Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let txn = |id: &str, amount: f64, name: &str| {
+            json!({
+                "transaction_id": format!("{}-{}", id, item_id),
+                "account_id": plaid_account_id,
+                "amount": amount,
+                "date": "2024-01-15",
+                "name": name,
+                "merchant_name": null,
+                "pending": false
+            })
+        };
+
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [txn("txn-1", 25.0, "Grocery Store")],
+                "modified": [],
+                "removed": [],
+                "next_cursor": "cursor-1",
+                "has_more": true
+            }),
+        );
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [txn("txn-2", -1000.0, "Payroll")],
+                "modified": [],
+                "removed": [],
+                "next_cursor": "cursor-2",
+                "has_more": false
+            }),
+        );
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [],
+                "modified": [txn("txn-1", 30.0, "Grocery Store")],
+                "removed": [{ "transaction_id": format!("txn-2-{}", item_id) }],
+                "next_cursor": "cursor-3",
+                "has_more": false
+            }),
+        );
+
+        let client = mock.client();
+
+        let first =
+            alm::plaid::sync::sync_transactions(&ctx.pool, &client, &ctx.keys, item_id).await;
+        assert!(first.is_ok());
+
+        let cursor = sqlx::query_scalar!(
+            "SELECT transactions_cursor FROM plaid_items WHERE id = $1",
+            item_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(cursor.as_deref(), Some("cursor-2"));
+
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transactions t JOIN accounts a ON t.account_id = a.id WHERE a.user_id = $1",
+            ctx.test_user_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(count, Some(2));
+
+        // txn-1 is split, then its amount changes so the splits no longer fit.
+        let txn_1 = format!("txn-1-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO transaction_splits (transaction_id, amount)
+             SELECT id, -12.50 FROM transactions WHERE plaid_transaction_id = $1
+             UNION ALL
+             SELECT id, -12.50 FROM transactions WHERE plaid_transaction_id = $1",
+            txn_1
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let second =
+            alm::plaid::sync::sync_transactions(&ctx.pool, &client, &ctx.keys, item_id).await;
+        assert!(second.is_ok());
+
+        let requests = mock.requests("/transactions/sync");
+        assert!(requests[0].get("cursor").is_none());
+        assert_eq!(requests[1]["cursor"], "cursor-1");
+        assert_eq!(requests[2]["cursor"], "cursor-2");
+
+        let remaining = sqlx::query!(
+            "SELECT t.plaid_transaction_id, t.amount
+             FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1",
+            ctx.test_user_id
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(
+            remaining[0].plaid_transaction_id.as_deref(),
+            Some(format!("txn-1-{}", item_id).as_str())
+        );
+        assert_eq!(remaining[0].amount, dec!(-30.0));
+
+        let splits = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transaction_splits s
+             JOIN transactions t ON s.transaction_id = t.id
+             WHERE t.plaid_transaction_id = $1",
+            txn_1
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(splits, Some(0));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_sync_transactions_maps_plaid_categories() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+
+        let item_id = Uuid::new_v4();
+        let plaid_item_id = format!("item-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            plaid_item_id,
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let plaid_account_id = format!("acc-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, account_type)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            Uuid::new_v4(),
+            ctx.test_user_id,
+            plaid_account_id,
+            plaid_item_id,
+            "Plaid Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let defaults: HashMap<String, Uuid> =
+            sqlx::query!("SELECT name, id FROM categories WHERE is_default = true")
+                .fetch_all(&ctx.pool)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.name, c.id))
+                .collect();
+        let groceries = defaults["Groceries"];
+        let dining = defaults["Dining Out"];
+        let shopping = defaults["Shopping"];
+
+        // The user's rule wins over Plaid's category.
+        sqlx::query!(
+            "INSERT INTO categorization_rules (user_id, name, merchant_pattern, set_category_id)
+             VALUES ($1, 'Costco is shopping', 'costco', $2)",
+            ctx.test_user_id,
+            shopping
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let txn = |id: &str, merchant: &str, primary: &str, detailed: &str| {
+            json!({
+                "transaction_id": format!("{}-{}", id, item_id),
+                "account_id": plaid_account_id,
+                "amount": 20.0,
+                "date": "2024-01-15",
+                "name": merchant,
+                "merchant_name": merchant,
+                "pending": false,
+                "personal_finance_category": { "primary": primary, "detailed": detailed }
+            })
+        };
+
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [
+                    txn("txn-1", "Trader Joe's", "FOOD_AND_DRINK", "FOOD_AND_DRINK_GROCERIES"),
+                    txn("txn-2", "Blue Bottle", "FOOD_AND_DRINK", "FOOD_AND_DRINK_COFFEE"),
+                    txn("txn-3", "Costco", "FOOD_AND_DRINK", "FOOD_AND_DRINK_GROCERIES"),
+                    txn("txn-4", "Venmo", "TRANSFER_OUT", "TRANSFER_OUT_ACCOUNT_TRANSFER")
+                ],
+                "modified": [],
+                "removed": [],
+                "next_cursor": "cursor-1",
+                "has_more": false
+            }),
+        );
+
+        alm::plaid::sync::sync_transactions(&ctx.pool, &mock.client(), &ctx.keys, item_id)
+            .await
+            .unwrap();
+
+        let imported = sqlx::query!(
+            "SELECT t.description, t.category_id, t.plaid_category_detailed
+             FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1
+             ORDER BY t.plaid_transaction_id",
+            ctx.test_user_id
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(imported.len(), 4);
+        // A detailed mapping, a fallback to the primary one, a rule, and no
+        // mapping at all.
+        assert_eq!(imported[0].category_id, Some(groceries));
+        assert_eq!(imported[1].category_id, Some(dining));
+        assert_eq!(imported[2].category_id, Some(shopping));
+        assert_eq!(imported[3].category_id, None);
+        assert_eq!(
+            imported[3].plaid_category_detailed.as_deref(),
+            Some("TRANSFER_OUT_ACCOUNT_TRANSFER")
+        );
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_sync_item_refreshes_balances() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+
+        let item_id = Uuid::new_v4();
+        let plaid_item_id = format!("item-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            plaid_item_id,
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let account_id = Uuid::new_v4();
+        let plaid_account_id = format!("acc-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, account_type)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            account_id,
+            ctx.test_user_id,
+            plaid_account_id,
+            plaid_item_id,
+            "Plaid Savings",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        mock.respond(
+            "/accounts/get",
+            json!({
+                "accounts": [{
+                    "account_id": plaid_account_id,
+                    "name": "Plaid Savings",
+                    "type": "depository",
+                    "subtype": "savings",
+                    "mask": "1111",
+                    "balances": {
+                        "current": 250.75,
+                        "available": 200.0,
+                        "iso_currency_code": "EUR"
+                    }
+                }]
+            }),
+        );
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [],
+                "modified": [],
+                "removed": [],
+                "next_cursor": "cursor-1",
+                "has_more": false
+            }),
+        );
+
+        let result =
+            alm::plaid::sync::sync_item(&ctx.pool, &mock.client(), &ctx.keys, item_id).await;
+        assert!(result.is_ok());
+
+        let account = sqlx::query!(
+            "SELECT balance, available_balance, currency, account_subtype, mask, last_synced
+             FROM accounts WHERE id = $1",
+            account_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(account.balance, dec!(250.75));
+        assert_eq!(account.available_balance, Some(dec!(200.0)));
+        assert_eq!(account.currency, "EUR");
+        assert_eq!(account.account_subtype.as_deref(), Some("savings"));
+        assert_eq!(account.mask.as_deref(), Some("1111"));
+        assert!(account.last_synced.is_some());
+
+        ctx.cleanup().await;
+    }
+
+    fn plaid_error(error_type: &str, error_code: &str) -> serde_json::Value {
+        json!({
+            "error_type": error_type,
+            "error_code": error_code,
+            "error_message": "something went wrong",
+            "display_message": null,
+            "request_id": "req-1"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_decode_plaid_errors() {
+        let mock = MockPlaid::start().await;
+        mock.respond_with_status(
+            "/accounts/get",
+            400,
+            plaid_error("ITEM_ERROR", "ITEM_LOGIN_REQUIRED"),
+        );
+        mock.respond_with_status(
+            "/item/get",
+            429,
+            plaid_error("RATE_LIMIT_EXCEEDED", "ACCOUNTS_LIMIT"),
+        );
+        mock.respond_with_status(
+            "/transactions/sync",
+            400,
+            plaid_error("INSTITUTION_ERROR", "INSTITUTION_DOWN"),
+        );
+        mock.respond_with_status(
+            "/item/public_token/exchange",
+            400,
+            plaid_error("INVALID_INPUT", "INVALID_PUBLIC_TOKEN"),
+        );
+
+        let client = mock.client();
+
+        let login = client.get_accounts("access-sandbox-abc").await;
+        assert!(matches!(login, Err(PlaidError::ItemLoginRequired(_))));
+
+        let rate_limited = client.get_item("access-sandbox-abc").await;
+        assert!(matches!(rate_limited, Err(PlaidError::RateLimited(_))));
+
+        let down = client.transactions_sync("access-sandbox-abc", None).await;
+        assert!(matches!(down, Err(PlaidError::InstitutionUnavailable(_))));
+
+        match client.exchange_public_token("public-sandbox-bad").await {
+            Err(PlaidError::Api(body)) => {
+                assert_eq!(body.error_type, "INVALID_INPUT");
+                assert_eq!(body.error_code, "INVALID_PUBLIC_TOKEN");
+            }
+            _ => panic!("expected a Plaid API error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_plaid_error() {
+        let mock = MockPlaid::start().await;
+        mock.respond_with_status("/accounts/get", 502, json!("Bad Gateway"));
+
+        let result = mock.client().get_accounts("access-sandbox-abc").await;
+
+        assert!(matches!(
+            result,
+            Err(PlaidError::UnexpectedResponse { status, .. }) if status.as_u16() == 502
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sync_item_marks_login_required() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+
+        let item_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            format!("item-{}", item_id),
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        mock.respond_with_status(
+            "/accounts/get",
+            400,
+            plaid_error("ITEM_ERROR", "ITEM_LOGIN_REQUIRED"),
+        );
+
+        let result =
+            alm::plaid::sync::sync_item(&ctx.pool, &mock.client(), &ctx.keys, item_id).await;
+        assert!(result.is_err());
+
+        let status = sqlx::query_scalar!("SELECT status FROM plaid_items WHERE id = $1", item_id)
+            .fetch_one(&ctx.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(status, "login_required");
+
+        ctx.cleanup().await;
+    }
+
+    fn mock_exchange(mock: &MockPlaid, plaid_item_id: &str) {
+        mock.respond(
+            "/item/public_token/exchange",
+            json!({
+                "access_token": "access-sandbox-new",
+                "item_id": plaid_item_id,
+                "request_id": "req-exchange"
+            }),
+        );
+        mock.respond(
+            "/item/get",
+            json!({ "item": { "institution_id": "ins_unknown" }, "request_id": "req-item" }),
+        );
+        mock.respond("/item/remove", json!({ "request_id": "req-remove" }));
+    }
+
+    #[tokio::test]
+    async fn test_link_item_without_institution_metadata() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+        let plaid_item_id = format!("item-{}", Uuid::new_v4());
+
+        // The institution lookup fails but the item is still linked.
+        mock_exchange(&mock, &plaid_item_id);
+        mock.respond(
+            "/accounts/get",
+            json!({
+                "accounts": [{
+                    "account_id": format!("acc-{}", plaid_item_id),
+                    "name": "Plaid Checking",
+                    "type": "depository",
+                    "balances": { "current": 110.5, "iso_currency_code": "USD" }
+                }]
+            }),
+        );
+
+        let linked = alm::plaid::link::exchange(
+            &ctx.pool,
+            &mock.client(),
+            &ctx.keys,
+            ctx.test_user_id,
+            "public-sandbox-abc",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(linked.institution_name, "ins_unknown");
+        assert_eq!(linked.accounts.len(), 1);
+        assert!(mock.requests("/item/remove").is_empty());
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_failed_link_removes_item() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+        let plaid_item_id = format!("item-{}", Uuid::new_v4());
+
+        mock_exchange(&mock, &plaid_item_id);
+        mock.respond_with_status(
+            "/accounts/get",
+            500,
+            plaid_error("API_ERROR", "INTERNAL_SERVER_ERROR"),
+        );
+
+        let result = alm::plaid::link::exchange(
+            &ctx.pool,
+            &mock.client(),
+            &ctx.keys,
+            ctx.test_user_id,
+            "public-sandbox-abc",
+        )
+        .await;
+        assert!(result.is_err());
+
+        let removed = mock.requests("/item/remove");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0]["access_token"], "access-sandbox-new");
+
+        let stored = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM plaid_items WHERE plaid_item_id = $1",
+            plaid_item_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(stored, Some(0));
+
+        ctx.cleanup().await;
+    }
+}
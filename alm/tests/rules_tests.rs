@@ -0,0 +1,381 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::{plaid_mock::MockPlaid, TestContext};
+    use alm::rules::{
+        suggestions::{tokens, SuggestionModel},
+        RuleInput, RuleSet,
+    };
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    async fn default_category(ctx: &TestContext, name: &str) -> Uuid {
+        sqlx::query_scalar!(
+            "SELECT id FROM categories WHERE name = $1 AND is_default = true LIMIT 1",
+            name
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_rule(
+        ctx: &TestContext,
+        priority: i32,
+        match_type: &str,
+        merchant_pattern: Option<&str>,
+        description_pattern: Option<&str>,
+        amount_range: (Option<Decimal>, Option<Decimal>),
+        account_id: Option<Uuid>,
+        set_category_id: Option<Uuid>,
+        set_description: Option<&str>,
+    ) {
+        sqlx::query!(
+            "INSERT INTO categorization_rules
+                (user_id, name, priority, match_type, merchant_pattern, description_pattern,
+                 min_amount, max_amount, account_id, set_category_id, set_description)
+             VALUES ($1, 'Test rule', $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            ctx.test_user_id,
+            priority,
+            match_type,
+            merchant_pattern,
+            description_pattern,
+            amount_range.0,
+            amount_range.1,
+            account_id,
+            set_category_id,
+            set_description
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rules_match_in_priority_order() {
+        let ctx = TestContext::new().await;
+
+        let groceries = default_category(&ctx, "Groceries").await;
+        let dining = default_category(&ctx, "Dining Out").await;
+        let transport = default_category(&ctx, "Transportation").await;
+        let account_id = Uuid::new_v4();
+        let other_account_id = Uuid::new_v4();
+
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Test Account",
+            "checking"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        insert_rule(
+            &ctx,
+            0,
+            "contains",
+            Some("whole foods"),
+            None,
+            (None, None),
+            None,
+            Some(groceries),
+            Some("Whole Foods"),
+        )
+        .await;
+        // Higher priority: small Whole Foods purchases are lunch, but the
+        // rename still comes from the rule above.
+        insert_rule(
+            &ctx,
+            10,
+            "contains",
+            Some("whole foods"),
+            None,
+            (None, Some(dec!(15.00))),
+            None,
+            Some(dining),
+            None,
+        )
+        .await;
+        insert_rule(
+            &ctx,
+            0,
+            "regex",
+            None,
+            Some(r"^uber\s+\*?trip"),
+            (None, None),
+            Some(account_id),
+            Some(transport),
+            None,
+        )
+        .await;
+
+        let rules = RuleSet::load(&ctx.pool, ctx.test_user_id).await.unwrap();
+
+        let input = |merchant: Option<&'static str>, description: &'static str, amount, account| {
+            RuleInput {
+                account_id: account,
+                description,
+                merchant_name: merchant,
+                amount,
+            }
+        };
+
+        let big_shop = rules.apply(&input(
+            Some("WHOLE FOODS MARKET"),
+            "WFM #123",
+            dec!(-84.20),
+            account_id,
+        ));
+        assert_eq!(big_shop.category_id, Some(groceries));
+        assert_eq!(big_shop.description.as_deref(), Some("Whole Foods"));
+
+        let lunch = rules.apply(&input(
+            Some("Whole Foods Market"),
+            "WFM #123",
+            dec!(-12.50),
+            account_id,
+        ));
+        assert_eq!(lunch.category_id, Some(dining));
+        assert_eq!(lunch.description.as_deref(), Some("Whole Foods"));
+
+        let ride = rules.apply(&input(None, "UBER *TRIP 8XJ2", dec!(-23.00), account_id));
+        assert_eq!(ride.category_id, Some(transport));
+        assert_eq!(ride.description, None);
+
+        // The ride rule is limited to one account.
+        let elsewhere = rules.apply(&input(
+            None,
+            "UBER *TRIP 8XJ2",
+            dec!(-23.00),
+            other_account_id,
+        ));
+        assert_eq!(elsewhere, Default::default());
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_plaid_import_applies_rules_to_new_transactions() {
+        let ctx = TestContext::new().await;
+        let mock = MockPlaid::start().await;
+
+        let subscriptions = default_category(&ctx, "Subscriptions").await;
+        insert_rule(
+            &ctx,
+            0,
+            "contains",
+            Some("netflix"),
+            None,
+            (None, None),
+            None,
+            Some(subscriptions),
+            Some("Netflix"),
+        )
+        .await;
+
+        let item_id = Uuid::new_v4();
+        let plaid_item_id = format!("item-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO plaid_items (id, user_id, plaid_access_token, plaid_item_id, institution_id, institution_name)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            item_id,
+            ctx.test_user_id,
+            "access-sandbox-abc",
+            plaid_item_id,
+            "ins_109508",
+            "ins_109508"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let plaid_account_id = format!("acc-{}", item_id);
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, plaid_account_id, plaid_item_id, account_name, account_type)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            Uuid::new_v4(),
+            ctx.test_user_id,
+            plaid_account_id,
+            plaid_item_id,
+            "Plaid Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let txn = |amount: f64| {
+            json!({
+                "transaction_id": format!("txn-{}", item_id),
+                "account_id": plaid_account_id,
+                "amount": amount,
+                "date": "2024-01-15",
+                "name": "NETFLIX.COM 866-579-7172",
+                "merchant_name": "Netflix",
+                "pending": false
+            })
+        };
+
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [txn(15.49)],
+                "modified": [],
+                "removed": [],
+                "next_cursor": "cursor-1",
+                "has_more": false
+            }),
+        );
+        mock.respond(
+            "/transactions/sync",
+            json!({
+                "added": [],
+                "modified": [txn(17.99)],
+                "removed": [],
+                "next_cursor": "cursor-2",
+                "has_more": false
+            }),
+        );
+
+        let client = mock.client();
+        alm::plaid::sync::sync_transactions(&ctx.pool, &client, &ctx.keys, item_id)
+            .await
+            .unwrap();
+
+        let plaid_transaction_id = format!("txn-{}", item_id);
+        let imported = sqlx::query!(
+            "SELECT description, category_id FROM transactions WHERE plaid_transaction_id = $1",
+            plaid_transaction_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(imported.description, "Netflix");
+        assert_eq!(imported.category_id, Some(subscriptions));
+
+        // A later edit by the user survives the transaction being modified.
+        sqlx::query!(
+            "UPDATE transactions SET description = 'Family plan' WHERE plaid_transaction_id = $1",
+            plaid_transaction_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        alm::plaid::sync::sync_transactions(&ctx.pool, &client, &ctx.keys, item_id)
+            .await
+            .unwrap();
+
+        let modified = sqlx::query!(
+            "SELECT description, amount FROM transactions WHERE plaid_transaction_id = $1",
+            plaid_transaction_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(modified.description, "Family plan");
+        assert_eq!(modified.amount, dec!(-17.99));
+
+        ctx.cleanup().await;
+    }
+
+    #[test]
+    fn test_description_tokens_drop_noise() {
+        assert_eq!(
+            tokens("POS DEBIT 4431 WHOLE FOODS #10233 Austin TX whole"),
+            vec!["whole", "foods", "austin"]
+        );
+    }
+
+    #[test]
+    fn test_suggestions_weigh_merchant_words_and_amount() {
+        let groceries = Uuid::new_v4();
+        let dining = Uuid::new_v4();
+
+        let mut model = SuggestionModel::default();
+        for _ in 0..3 {
+            model.learn(
+                Some("Whole Foods"),
+                "WHOLE FOODS MARKET",
+                dec!(-150.00),
+                groceries,
+            );
+        }
+        model.learn(
+            Some("Whole Foods"),
+            "WHOLE FOODS MARKET",
+            dec!(-12.00),
+            dining,
+        );
+
+        let weekly_shop = model.suggest(Some("WHOLE FOODS"), "WFM 0042", dec!(-140.00));
+        assert_eq!(weekly_shop[0].category_id, groceries);
+        assert_eq!(weekly_shop[0].confidence, 0.64);
+        assert_eq!(weekly_shop[1].category_id, dining);
+
+        // Small purchases there have been lunch before.
+        let lunch = model.suggest(Some("Whole Foods"), "WFM 0042", dec!(-11.50));
+        assert!(lunch[1].confidence > weekly_shop[1].confidence);
+
+        // No merchant: the description words alone still point somewhere.
+        let by_words = model.suggest(None, "WHOLE FOODS #77", dec!(-60.00));
+        assert_eq!(by_words[0].category_id, groceries);
+
+        assert!(model
+            .suggest(Some("Shell"), "SHELL OIL 5741", dec!(-40.00))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_suggestion_model_learns_from_categorized_history() {
+        let ctx = TestContext::new().await;
+
+        let transport = default_category(&ctx, "Transportation").await;
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Test Account",
+            "checking"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        for (description, category_id) in [
+            ("UBER TRIP HELP.UBER.COM", Some(transport)),
+            ("UBER TRIP HELP.UBER.COM", Some(transport)),
+            ("UBER TRIP HELP.UBER.COM", None),
+        ] {
+            sqlx::query!(
+                "INSERT INTO transactions (account_id, date, amount, description, category_id)
+                 VALUES ($1, '2024-01-15', $2, $3, $4)",
+                account_id,
+                dec!(-18.40),
+                description,
+                category_id
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        let model = SuggestionModel::load(&ctx.pool, ctx.test_user_id)
+            .await
+            .unwrap();
+        let suggestions = model.suggest(None, "UBER TRIP HELP.UBER.COM", dec!(-21.00));
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].category_id, transport);
+        assert!(suggestions[0].confidence >= 0.6);
+
+        ctx.cleanup().await;
+    }
+}
@@ -2,6 +2,8 @@
 mod tests {
     use super::super::common::TestContext;
     use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -16,7 +18,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -32,7 +34,7 @@ mod tests {
             transaction_id,
             account_id,
             date,
-            -50.00,
+            dec!(-50.00),
             "Grocery Store",
             false
         )
@@ -50,7 +52,7 @@ mod tests {
         .unwrap();
 
         assert_eq!(transaction.description, "Grocery Store");
-        assert_eq!(transaction.amount, -50.00);
+        assert_eq!(transaction.amount, dec!(-50.00));
 
         ctx.cleanup().await;
     }
@@ -67,7 +69,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -90,7 +92,7 @@ mod tests {
             transaction_id,
             account_id,
             date,
-            -50.00,
+            dec!(-50.00),
             "Grocery Store",
             false
         )
@@ -132,7 +134,7 @@ mod tests {
             ctx.test_user_id,
             "Test Account",
             "checking",
-            1000.00,
+            dec!(1000.00),
             "USD"
         )
         .execute(&ctx.pool)
@@ -148,7 +150,7 @@ mod tests {
             Uuid::new_v4(),
             account_id,
             date1,
-            -50.00,
+            dec!(-50.00),
             "January Transaction",
             false
         )
@@ -162,7 +164,7 @@ mod tests {
             Uuid::new_v4(),
             account_id,
             date2,
-            -75.00,
+            dec!(-75.00),
             "February Transaction",
             false
         )
@@ -188,4 +190,452 @@ mod tests {
 
         ctx.cleanup().await;
     }
+
+    #[tokio::test]
+    async fn test_search_and_paginate_transactions() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type, balance, currency)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            account_id,
+            ctx.test_user_id,
+            "Test Account",
+            "checking",
+            dec!(1000.00),
+            "USD"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let rows = [
+            (
+                "COFFEE SHOP #123",
+                Some("Blue Bottle Coffee"),
+                dec!(-4.50),
+                10,
+            ),
+            ("Weekly groceries", Some("Whole Foods"), dec!(-82.10), 11),
+            ("Coffee beans", None, dec!(-18.00), 12),
+            ("Payroll", Some("ACME Corp"), dec!(2500.00), 12),
+        ];
+
+        for (description, merchant_name, amount, day) in rows {
+            sqlx::query!(
+                "INSERT INTO transactions (id, account_id, date, amount, description, merchant_name, pending)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                Uuid::new_v4(),
+                account_id,
+                NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+                amount,
+                description,
+                merchant_name,
+                false
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        // Same document and query function as list_transactions; stemming
+        // matches "coffees" against "Coffee".
+        let matches = sqlx::query!(
+            "SELECT t.description FROM transactions t
+             WHERE t.account_id = $1
+             AND to_tsvector('english', t.description || ' ' || COALESCE(t.merchant_name, ''))
+                 @@ websearch_to_tsquery('english', $2)
+             ORDER BY t.date DESC, t.id DESC",
+            account_id,
+            "coffees"
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].description, "Coffee beans");
+
+        // Keyset pagination: each page starts strictly after the last row of
+        // the previous one, so two rows sharing a date are neither skipped
+        // nor repeated.
+        let mut seen = Vec::new();
+        let mut cursor: Option<(NaiveDate, Uuid)> = None;
+
+        loop {
+            let (after_date, after_id) =
+                cursor.unwrap_or((NaiveDate::from_ymd_opt(9999, 12, 31).unwrap(), Uuid::max()));
+
+            let page = sqlx::query!(
+                "SELECT t.id, t.date, t.description FROM transactions t
+                 WHERE t.account_id = $1 AND (t.date, t.id) < ($2, $3)
+                 ORDER BY t.date DESC, t.id DESC
+                 LIMIT 3",
+                account_id,
+                after_date,
+                after_id
+            )
+            .fetch_all(&ctx.pool)
+            .await
+            .unwrap();
+
+            if page.is_empty() {
+                break;
+            }
+
+            let last = page.last().unwrap();
+            cursor = Some((last.date, last.id));
+            seen.extend(page.into_iter().map(|t| t.description));
+        }
+
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[3], "COFFEE SHOP #123");
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_transactions() {
+        let ctx = TestContext::new().await;
+        let other = TestContext::new().await;
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let mut ids = Vec::new();
+
+        for (user_id, description) in [
+            (ctx.test_user_id, "Coffee"),
+            (ctx.test_user_id, "Lunch"),
+            (other.test_user_id, "Not mine"),
+        ] {
+            let account_id = Uuid::new_v4();
+            sqlx::query!(
+                "INSERT INTO accounts (id, user_id, account_name, account_type, balance, currency) 
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                account_id,
+                user_id,
+                "Test Account",
+                "checking",
+                dec!(1000.00),
+                "USD"
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+            let id = Uuid::new_v4();
+            sqlx::query!(
+                "INSERT INTO transactions (id, account_id, date, amount, description, pending, notes) 
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                id,
+                account_id,
+                date,
+                dec!(-5.00),
+                description,
+                false,
+                "old note"
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+            ids.push(id);
+        }
+
+        let owned = sqlx::query_scalar!(
+            "SELECT t.id FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1 AND t.id = ANY($2)",
+            ctx.test_user_id,
+            &ids
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(owned.len(), 2);
+        assert!(!owned.contains(&ids[2]));
+
+        let category_id = sqlx::query_scalar!(
+            "SELECT id FROM categories WHERE name = 'Groceries' AND is_default = true LIMIT 1"
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+
+        // Set the category, clear the notes and leave descriptions untouched.
+        let update_category: Option<Option<Uuid>> = Some(Some(category_id));
+        let update_notes: Option<Option<String>> = Some(None);
+
+        let result = sqlx::query!(
+            "UPDATE transactions SET
+                category_id = CASE WHEN $2 THEN $3 ELSE category_id END,
+                description = COALESCE($4, description),
+                notes = CASE WHEN $5 THEN $6 ELSE notes END,
+                updated_at = NOW()
+             WHERE id = ANY($1)",
+            &owned,
+            update_category.is_some(),
+            update_category.flatten(),
+            None::<String>,
+            update_notes.is_some(),
+            update_notes.flatten()
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(result.rows_affected(), 2);
+
+        let rows = sqlx::query!(
+            "SELECT id, category_id, description, notes FROM transactions
+             WHERE id = ANY($1) ORDER BY description",
+            &ids
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        for row in &rows {
+            if row.id == ids[2] {
+                assert_eq!(row.category_id, None);
+                assert_eq!(row.notes.as_deref(), Some("old note"));
+            } else {
+                assert_eq!(row.category_id, Some(category_id));
+                assert_eq!(row.notes, None);
+            }
+        }
+        assert_eq!(rows[0].description, "Coffee");
+
+        other.cleanup().await;
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update_filter_needs_a_criterion() {
+        let ctx = TestContext::new().await;
+
+        let mut accounts = Vec::new();
+        for name in ["Checking", "Savings"] {
+            let account_id = Uuid::new_v4();
+            sqlx::query!(
+                "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+                account_id,
+                ctx.test_user_id,
+                name,
+                "depository"
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+
+            for description in ["Coffee", "Rent"] {
+                sqlx::query!(
+                    "INSERT INTO transactions (account_id, date, amount, description)
+                     VALUES ($1, $2, $3, $4)",
+                    account_id,
+                    NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+                    dec!(-5.00),
+                    description
+                )
+                .execute(&ctx.pool)
+                .await
+                .unwrap();
+            }
+
+            accounts.push(account_id);
+        }
+
+        // The handler appends the filter's predicates to this query and
+        // rejects a filter that adds none.
+        let select = |filters: &str| {
+            format!(
+                "SELECT t.id FROM transactions t
+                 JOIN accounts a ON t.account_id = a.id
+                 WHERE a.user_id = $1{} FOR UPDATE OF t",
+                filters
+            )
+        };
+
+        let unfiltered: Vec<(Uuid,)> = sqlx::query_as(&select(""))
+            .bind(ctx.test_user_id)
+            .fetch_all(&ctx.pool)
+            .await
+            .unwrap();
+        assert_eq!(unfiltered.len(), 4);
+
+        let filtered: Vec<(Uuid,)> = sqlx::query_as(&select(" AND t.account_id = $2"))
+            .bind(ctx.test_user_id)
+            .bind(accounts[0])
+            .fetch_all(&ctx.pool)
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 2);
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_split_transactions_allocate_by_category() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type, balance, currency) 
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            account_id,
+            ctx.test_user_id,
+            "Test Account",
+            "checking",
+            dec!(1000.00),
+            "USD"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let category = |name: &'static str| {
+            sqlx::query_scalar!(
+                "SELECT id FROM categories WHERE name = $1 AND is_default = true LIMIT 1",
+                name
+            )
+            .fetch_one(&ctx.pool)
+        };
+        let groceries = category("Groceries").await.unwrap();
+        let health = category("Health").await.unwrap();
+        let shopping = category("Shopping").await.unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let costco = Uuid::new_v4();
+
+        for (id, amount, category_id) in [
+            (costco, dec!(-100.00), shopping),
+            (Uuid::new_v4(), dec!(-20.00), groceries),
+        ] {
+            sqlx::query!(
+                "INSERT INTO transactions (id, account_id, date, amount, description, category_id) 
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                id,
+                account_id,
+                date,
+                amount,
+                "Costco",
+                category_id
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        for (category_id, amount, memo) in [
+            (groceries, dec!(-60.00), "Food"),
+            (health, dec!(-40.00), "Pharmacy"),
+        ] {
+            sqlx::query!(
+                "INSERT INTO transaction_splits (transaction_id, category_id, amount, memo) 
+                 VALUES ($1, $2, $3, $4)",
+                costco,
+                category_id,
+                amount,
+                memo
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        let zero_split = sqlx::query!(
+            "INSERT INTO transaction_splits (transaction_id, amount) VALUES ($1, 0)",
+            costco
+        )
+        .execute(&ctx.pool)
+        .await;
+        assert!(zero_split.is_err());
+
+        let allocations = sqlx::query!(
+            "SELECT t.category_id, SUM(t.amount) as \"total!\"
+             FROM transaction_allocations t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1
+             GROUP BY t.category_id",
+            ctx.test_user_id
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        let total = |category_id| {
+            allocations
+                .iter()
+                .find(|a| a.category_id == Some(category_id))
+                .map(|a| a.total)
+        };
+
+        assert_eq!(allocations.len(), 2);
+        assert_eq!(total(groceries), Some(dec!(-80.00)));
+        assert_eq!(total(health), Some(dec!(-40.00)));
+        assert_eq!(total(shopping), None);
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_split_amounts_are_whole_cents() {
+        let ctx = TestContext::new().await;
+
+        let account_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            account_id,
+            ctx.test_user_id,
+            "Checking",
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        let transaction_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO transactions (id, account_id, date, amount, description)
+             VALUES ($1, $2, $3, $4, $5)",
+            transaction_id,
+            account_id,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            dec!(-20.00),
+            "Pharmacy"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        // These add up to the parent but are rounded apart when stored, which
+        // is why validation rejects anything finer than a cent.
+        let splits = [dec!(-10.005), dec!(-9.995)];
+        assert_eq!(splits.iter().sum::<Decimal>(), dec!(-20.00));
+        assert!(splits.iter().all(|a| a.normalize().scale() > 2));
+        assert_eq!(dec!(-10.500).normalize().scale(), 1);
+
+        for amount in splits {
+            sqlx::query!(
+                "INSERT INTO transaction_splits (transaction_id, amount) VALUES ($1, $2)",
+                transaction_id,
+                amount
+            )
+            .execute(&ctx.pool)
+            .await
+            .unwrap();
+        }
+
+        let stored = sqlx::query_scalar!(
+            "SELECT SUM(amount) FROM transaction_splits WHERE transaction_id = $1",
+            transaction_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_ne!(stored, Some(dec!(-20.00)));
+
+        ctx.cleanup().await;
+    }
 }
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use super::super::common::TestContext;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    async fn account(ctx: &TestContext, name: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO accounts (id, user_id, account_name, account_type) VALUES ($1, $2, $3, $4)",
+            id,
+            ctx.test_user_id,
+            name,
+            "depository"
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn transaction(
+        ctx: &TestContext,
+        account_id: Uuid,
+        day: u32,
+        amount: Decimal,
+        pending: bool,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO transactions (id, account_id, date, amount, description, pending)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            id,
+            account_id,
+            NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            amount,
+            "Transfer",
+            pending
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_detect_transfers() {
+        let ctx = TestContext::new().await;
+
+        let checking = account(&ctx, "Checking").await;
+        let savings = account(&ctx, "Savings").await;
+        let card = account(&ctx, "Credit Card").await;
+
+        let to_savings = transaction(&ctx, checking, 10, dec!(-500.00), false).await;
+        let from_checking = transaction(&ctx, savings, 11, dec!(500.00), false).await;
+        // Two candidate inflows; the one posting on the same day wins.
+        let card_payment = transaction(&ctx, checking, 15, dec!(-75.20), false).await;
+        let card_credit_late = transaction(&ctx, card, 17, dec!(75.20), false).await;
+        let card_credit = transaction(&ctx, card, 15, dec!(75.20), false).await;
+        // Too far apart, pending, or within one account.
+        transaction(&ctx, checking, 1, dec!(-40.00), false).await;
+        transaction(&ctx, savings, 9, dec!(40.00), false).await;
+        transaction(&ctx, checking, 20, dec!(-60.00), true).await;
+        transaction(&ctx, savings, 20, dec!(60.00), false).await;
+        transaction(&ctx, checking, 25, dec!(-12.00), false).await;
+        transaction(&ctx, checking, 25, dec!(12.00), false).await;
+
+        let linked = alm::transfers::detect(&ctx.pool, ctx.test_user_id)
+            .await
+            .unwrap();
+        assert_eq!(linked, 2);
+
+        let transfers = sqlx::query!(
+            "SELECT outflow_transaction_id, inflow_transaction_id FROM transfers
+             WHERE user_id = $1 AND status = 'linked'",
+            ctx.test_user_id
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .unwrap();
+
+        let pairs: Vec<(Uuid, Uuid)> = transfers
+            .iter()
+            .map(|t| (t.outflow_transaction_id, t.inflow_transaction_id))
+            .collect();
+        assert!(pairs.contains(&(to_savings, from_checking)));
+        assert!(pairs.contains(&(card_payment, card_credit)));
+        assert!(!pairs.iter().any(|(_, i)| *i == card_credit_late));
+
+        // Running again finds nothing new.
+        let again = alm::transfers::detect(&ctx.pool, ctx.test_user_id)
+            .await
+            .unwrap();
+        assert_eq!(again, 0);
+
+        // Linked transfers drop out of reports.
+        let reported = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transaction_allocations t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1",
+            ctx.test_user_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(reported, Some(7));
+
+        ctx.cleanup().await;
+    }
+
+    #[tokio::test]
+    async fn test_unlinked_transfers_are_not_detected_again() {
+        let ctx = TestContext::new().await;
+
+        let checking = account(&ctx, "Checking").await;
+        let savings = account(&ctx, "Savings").await;
+
+        transaction(&ctx, checking, 10, dec!(-250.00), false).await;
+        transaction(&ctx, savings, 10, dec!(250.00), false).await;
+
+        assert_eq!(
+            alm::transfers::detect(&ctx.pool, ctx.test_user_id)
+                .await
+                .unwrap(),
+            1
+        );
+
+        sqlx::query!(
+            "UPDATE transfers SET status = 'unlinked' WHERE user_id = $1",
+            ctx.test_user_id
+        )
+        .execute(&ctx.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            alm::transfers::detect(&ctx.pool, ctx.test_user_id)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let reported = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM transaction_allocations t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1",
+            ctx.test_user_id
+        )
+        .fetch_one(&ctx.pool)
+        .await
+        .unwrap();
+        assert_eq!(reported, Some(2));
+
+        ctx.cleanup().await;
+    }
+}